@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_channel::oneshot;
+use serde_json::Value;
+
+use crate::client::{GraphQLRequest, GraphQLResponse};
+use crate::document::Summary;
+use crate::error::GraphQLError;
+use crate::runtime;
+use crate::types::Batching;
+
+pub(crate) type BatchResult = Result<GraphQLResponse<Value>, GraphQLError>;
+
+/// Operation waiting in a batch, with the channel its result is sent to
+pub(crate) struct Entry {
+  pub(crate) request: GraphQLRequest,
+  pub(crate) summary: Summary,
+  pub(crate) sender: oneshot::Sender<BatchResult>,
+}
+
+struct Queue {
+  entries: Vec<Entry>,
+  /// incremented each time a batch is taken, so a pending batch does not take the next one
+  generation: u64,
+}
+
+/// Batch a caller has to send once its operation is queued
+pub(crate) enum Batch {
+  /// started by the caller, sent when the window is over unless it was flushed because it was full
+  Pending(PendingBatch),
+  /// full, sent now
+  Full(Vec<Entry>),
+}
+
+impl Batch {
+  /// Wait for the end of the window of a pending batch and take its operations
+  pub(crate) async fn entries(self) -> Vec<Entry> {
+    match self {
+      Batch::Pending(pending) => {
+        runtime::sleep(pending.batcher.window).await;
+        pending.take()
+      }
+      Batch::Full(entries) => entries,
+    }
+  }
+}
+
+/// Collects the operations of concurrent queries into batches
+pub(crate) struct Batcher {
+  window: Duration,
+  max_size: usize,
+  queue: Mutex<Queue>,
+}
+
+impl Batcher {
+  pub(crate) fn new(batching: &Batching) -> Self {
+    Self {
+      window: Duration::from_millis(batching.window),
+      max_size: batching.max_size,
+      queue: Mutex::new(Queue {
+        entries: Vec::new(),
+        generation: 0,
+      }),
+    }
+  }
+
+  fn queue(&self) -> MutexGuard<'_, Queue> {
+    self.queue.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Queue an operation, the batch to send is returned to the caller starting it or filling it
+  pub(crate) fn push(
+    self: &Arc<Self>,
+    request: GraphQLRequest,
+    summary: Summary,
+  ) -> (oneshot::Receiver<BatchResult>, Option<Batch>) {
+    let (sender, receiver) = oneshot::channel();
+    let mut queue = self.queue();
+    queue.entries.push(Entry {
+      request,
+      summary,
+      sender,
+    });
+
+    let batch = if queue.entries.len() >= self.max_size {
+      queue.generation += 1;
+      Some(Batch::Full(std::mem::take(&mut queue.entries)))
+    } else if queue.entries.len() == 1 {
+      Some(Batch::Pending(PendingBatch {
+        batcher: self.clone(),
+        generation: queue.generation,
+        taken: false,
+      }))
+    } else {
+      None
+    };
+    (receiver, batch)
+  }
+
+  /// Take the operations of a pending batch, empty if it was already flushed because it was full
+  fn take(&self, generation: u64) -> Vec<Entry> {
+    let mut queue = self.queue();
+    if queue.generation != generation {
+      return Vec::new();
+    }
+    queue.generation += 1;
+    std::mem::take(&mut queue.entries)
+  }
+}
+
+/// Batch waiting for the end of the window.
+/// If it is dropped before it is sent, its operations are dropped too so the callers stop waiting.
+pub(crate) struct PendingBatch {
+  batcher: Arc<Batcher>,
+  generation: u64,
+  taken: bool,
+}
+
+impl PendingBatch {
+  fn take(mut self) -> Vec<Entry> {
+    self.taken = true;
+    self.batcher.take(self.generation)
+  }
+}
+
+impl Drop for PendingBatch {
+  fn drop(&mut self) {
+    if !self.taken {
+      drop(self.batcher.take(self.generation));
+    }
+  }
+}
@@ -1,61 +1,268 @@
 use std::collections::HashMap;
-#[cfg(not(target_arch = "wasm32"))]
-use std::convert::TryInto;
+use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use reqwest::{Client, Url};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 
-use crate::error::{GraphQLError, GraphQLErrorMessage};
-use crate::ClientConfig;
+#[cfg(feature = "batching")]
+use crate::batch::{Batcher, Entry};
+use crate::document::{self, Summary};
+use crate::error::{GraphQLError, GraphQLErrorKind, GraphQLErrorMessage};
+use crate::incremental::IncrementalStream;
+use crate::middleware::{Middleware, Next, NextStreaming};
+use crate::runtime;
+use crate::transport::{
+  HttpMethod, ReqwestTransport, Transport, TransportRequest, TransportResponse,
+  TransportStreamResponse,
+};
+use crate::types::{DocumentIdField, RequestOptions};
+use crate::upload::{self, Variables};
+use crate::{ClientConfig, SubscriptionStream};
 
-#[derive(Clone, Debug)]
-pub struct GQLClient {
+pub struct GQLClient<C = ReqwestTransport> {
   config: ClientConfig,
+  /// shared between clones, so batches can be sent from a task of their own
+  transport: Arc<C>,
+  middleware: Vec<Arc<dyn Middleware>>,
+  /// shared between clones, so their queries are batched together
+  #[cfg(feature = "batching")]
+  batcher: Option<Arc<Batcher>>,
+}
+
+impl<C> Clone for GQLClient<C> {
+  fn clone(&self) -> Self {
+    Self {
+      config: self.config.clone(),
+      transport: self.transport.clone(),
+      middleware: self.middleware.clone(),
+      #[cfg(feature = "batching")]
+      batcher: self.batcher.clone(),
+    }
+  }
+}
+
+impl<C: fmt::Debug> fmt::Debug for GQLClient<C> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut debug = f.debug_struct("GQLClient");
+    debug
+      .field("config", &self.config)
+      .field("transport", &self.transport)
+      .field("middleware", &self.middleware.len());
+    #[cfg(feature = "batching")]
+    debug.field("batching", &self.batcher.is_some());
+    debug.finish()
+  }
 }
 
 #[derive(Serialize)]
-struct RequestBody<T: Serialize> {
-  query: String,
-  variables: T,
+pub(crate) struct RequestBody<T: Serialize> {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) query: Option<String>,
+  /// id of a trusted document, sent instead of the query
+  #[serde(rename = "documentId", skip_serializing_if = "Option::is_none")]
+  pub(crate) document_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) id: Option<String>,
+  #[serde(rename = "operationName", skip_serializing_if = "Option::is_none")]
+  pub(crate) operation_name: Option<String>,
+  pub(crate) variables: T,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) extensions: Option<Map<String, Value>>,
+}
+
+impl<T: Serialize> RequestBody<T> {
+  pub(crate) fn new(query: &str, variables: T) -> Self {
+    Self {
+      query: Some(query.to_string()),
+      document_id: None,
+      id: None,
+      operation_name: None,
+      variables,
+      extensions: None,
+    }
+  }
+}
+
+/// Serialized request, sent as a json body or in the url of a GET request
+#[derive(Clone, Debug)]
+enum Payload {
+  Json(Vec<u8>),
+  Params(Vec<(&'static str, String)>),
+  Multipart { content_type: String, body: Vec<u8> },
 }
 
-#[derive(Deserialize, Debug)]
-struct GraphQLResponse<T> {
+impl Payload {
+  /// Encode a request body as url parameters, json values are serialized as strings
+  fn params<T: Serialize>(body: &RequestBody<T>) -> Result<Self, GraphQLError> {
+    let mut params = Vec::new();
+    if let Some(query) = &body.query {
+      params.push(("query", query.clone()));
+    }
+    if let Some(id) = &body.document_id {
+      params.push(("documentId", id.clone()));
+    }
+    if let Some(id) = &body.id {
+      params.push(("id", id.clone()));
+    }
+    if let Some(operation_name) = &body.operation_name {
+      params.push(("operationName", operation_name.clone()));
+    }
+    let variables = to_json(&body.variables)?;
+    if variables != b"null" {
+      params.push((
+        "variables",
+        String::from_utf8_lossy(&variables).into_owned(),
+      ));
+    }
+    if let Some(extensions) = &body.extensions {
+      params.push(("extensions", Value::Object(extensions.clone()).to_string()));
+    }
+    Ok(Payload::Params(params))
+  }
+
+  fn new<T: Serialize>(body: &RequestBody<T>, method: HttpMethod) -> Result<Self, GraphQLError> {
+    match method {
+      HttpMethod::Get => Self::params(body),
+      HttpMethod::Post => Ok(Payload::Json(to_json(body)?)),
+    }
+  }
+}
+
+/// Operation of a [`batch`](GQLClient::batch), or sent through the tower `Service` of the client
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+  pub query: String,
+  pub variables: Value,
+  /// operation of the document to execute, detected when the document has a single named operation
+  pub operation_name: Option<String>,
+}
+
+impl GraphQLRequest {
+  pub fn new(query: impl AsRef<str>) -> Self {
+    Self {
+      query: query.as_ref().to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_variables(mut self, variables: impl Serialize) -> Result<Self, GraphQLError> {
+    self.variables = serde_json::to_value(variables).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Serialize,
+        format!("Failed to serialize variables: {:?}", e),
+      )
+      .with_source(e)
+    })?;
+    Ok(self)
+  }
+
+  pub fn with_operation_name(mut self, operation_name: impl AsRef<str>) -> Self {
+    self.operation_name = Some(operation_name.as_ref().to_string());
+    self
+  }
+}
+
+/// GraphQL response, data may be partial when errors are present
+#[derive(Deserialize, Debug, Clone)]
+pub struct GraphQLResponse<T> {
   data: Option<T>,
   errors: Option<Vec<GraphQLErrorMessage>>,
 }
 
-impl GQLClient {
-  #[cfg(target_arch = "wasm32")]
-  fn client(&self) -> Result<Client, GraphQLError> {
-    Ok(Client::new())
+impl<T> GraphQLResponse<T> {
+  pub fn data(&self) -> Option<&T> {
+    self.data.as_ref()
   }
 
-  #[cfg(not(target_arch = "wasm32"))]
-  fn client(&self) -> Result<Client, GraphQLError> {
-    let mut builder = Client::builder().timeout(std::time::Duration::from_secs(
-      self.config.timeout.unwrap_or(5),
-    ));
-    if let Some(proxy) = &self.config.proxy {
-      builder = builder.proxy(proxy.clone().try_into()?);
+  /// GraphQL errors, empty if every field resolved
+  pub fn errors(&self) -> &[GraphQLErrorMessage] {
+    self.errors.as_deref().unwrap_or_default()
+  }
+
+  pub fn has_errors(&self) -> bool {
+    !self.errors().is_empty()
+  }
+
+  /// GraphQL errors with their extensions deserialized into `E`
+  pub fn errors_as<E>(&self) -> Result<Vec<GraphQLErrorMessage<E>>, GraphQLError>
+  where
+    E: for<'de> Deserialize<'de>,
+  {
+    self
+      .errors()
+      .iter()
+      .map(|err| err.extensions_as())
+      .collect()
+  }
+
+  pub fn into_data(self) -> Option<T> {
+    self.data
+  }
+
+  pub fn into_parts(self) -> (Option<T>, Vec<GraphQLErrorMessage>) {
+    (self.data, self.errors.unwrap_or_default())
+  }
+
+  /// Discard partial data if any error has been received
+  pub fn into_result(self) -> Result<Option<T>, GraphQLError> {
+    if self.has_errors() {
+      Err(GraphQLError::with_json(self.errors.unwrap_or_default()))
+    } else {
+      Ok(self.data)
     }
-    builder
-      .build()
-      .map_err(|e| GraphQLError::with_text(format!("Can not create client: {:?}", e)))
+  }
+}
+
+impl GraphQLResponse<Value> {
+  /// Deserialize the data, to read the results of a [`batch`](GQLClient::batch)
+  pub fn into_typed<K>(self) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    let data = match self.data {
+      Some(data) => serde_json::from_value(data).map_err(|e| {
+        GraphQLError::with_kind(
+          GraphQLErrorKind::Deserialize,
+          format!("Failed to parse data: {:?}", e),
+        )
+        .with_source(e)
+      })?,
+      None => None,
+    };
+    Ok(GraphQLResponse {
+      data,
+      errors: self.errors,
+    })
+  }
+}
+
+impl<T> GraphQLResponse<T>
+where
+  T: for<'de> Deserialize<'de>,
+{
+  /// Parse a streamed payload, GraphQL errors are returned as an error
+  pub(crate) fn from_value(payload: serde_json::Value) -> Result<T, GraphQLError> {
+    let response: GraphQLResponse<T> = serde_json::from_value(payload).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Deserialize,
+        format!("Failed to parse payload: {:?}", e),
+      )
+      .with_source(e)
+    })?;
+    response.into_result()?.ok_or_else(|| {
+      GraphQLError::with_kind(GraphQLErrorKind::NoData, "No data in subscription payload")
+    })
   }
 }
 
 impl GQLClient {
   pub fn new(endpoint: impl AsRef<str>) -> Self {
-    Self {
-      config: ClientConfig {
-        endpoint: endpoint.as_ref().to_string(),
-        timeout: None,
-        headers: Default::default(),
-        proxy: None,
-      },
-    }
+    Self::new_with_config(ClientConfig::new(endpoint))
   }
 
   pub fn new_with_headers(
@@ -65,24 +272,47 @@ impl GQLClient {
     let _headers: HashMap<String, String> = headers
       .iter()
       .map(|(name, value)| (name.to_string(), value.to_string()))
-      .into_iter()
       .collect();
+    Self::new_with_config(ClientConfig::new(endpoint).with_headers(_headers))
+  }
+
+  pub fn new_with_config(config: ClientConfig) -> Self {
+    let transport = ReqwestTransport::new(&config);
+    Self::new_with_transport(config, transport)
+  }
+
+  /// Create a client sharing the connection pool of an existing `reqwest::Client`.
+  /// The timeout and proxy from the config are ignored, configure them on the reqwest client instead.
+  pub fn new_with_client(config: ClientConfig, client: reqwest::Client) -> Self {
+    Self::new_with_transport(config, ReqwestTransport::with_client(client))
+  }
+}
+
+impl<C: Transport> GQLClient<C> {
+  /// Create a client which sends its requests through a custom [`Transport`]
+  pub fn new_with_transport(config: ClientConfig, transport: C) -> Self {
+    #[cfg(feature = "batching")]
+    let batcher = config
+      .batching
+      .as_ref()
+      .map(|batching| Arc::new(Batcher::new(batching)));
     Self {
-      config: ClientConfig {
-        endpoint: endpoint.as_ref().to_string(),
-        timeout: None,
-        headers: Some(_headers),
-        proxy: None,
-      },
+      config,
+      transport: Arc::new(transport),
+      middleware: Vec::new(),
+      #[cfg(feature = "batching")]
+      batcher,
     }
   }
 
-  pub fn new_with_config(config: ClientConfig) -> Self {
-    Self { config }
+  /// Add a middleware, run after the ones already added
+  pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+    self.middleware.push(Arc::new(middleware));
+    self
   }
 }
 
-impl GQLClient {
+impl<C: Transport + 'static> GQLClient<C> {
   pub async fn query<K>(&self, query: &str) -> Result<Option<K>, GraphQLError>
   where
     K: for<'de> Deserialize<'de>,
@@ -107,10 +337,13 @@ impl GQLClient {
   {
     match self.query_with_vars(query, variables).await? {
       Some(v) => Ok(v),
-      None => Err(GraphQLError::with_text(format!(
-        "No data from graphql server({}) for this query",
-        self.config.endpoint
-      ))),
+      None => Err(GraphQLError::with_kind(
+        GraphQLErrorKind::NoData,
+        format!(
+          "No data from graphql server({}) for this query",
+          self.config.endpoint
+        ),
+      )),
     }
   }
 
@@ -123,7 +356,441 @@ impl GQLClient {
     K: for<'de> Deserialize<'de>,
   {
     self
-      .query_with_vars_by_endpoint(&self.config.endpoint, query, variables)
+      .query_with_vars_response(query, variables)
+      .await?
+      .into_result()
+      .map_err(|e| e.with_query(query))
+  }
+
+  /// Like [`query`](Self::query), but GraphQL errors are returned along with the partial data
+  pub async fn query_response<K>(&self, query: &str) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    self.query_with_vars_response::<K, ()>(query, ()).await
+  }
+
+  /// Like [`query_with_vars`](Self::query_with_vars), but GraphQL errors are returned along with the partial data.
+  /// Only transport and HTTP failures are returned as an error.
+  pub async fn query_with_vars_response<K, T: Serialize>(
+    &self,
+    query: &str,
+    variables: T,
+  ) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    self
+      .query_with_options(query, variables, RequestOptions::default())
+      .await
+  }
+
+  /// Like [`query_with_vars_response`](Self::query_with_vars_response), with options for this request only
+  pub async fn query_with_options<K, T: Serialize>(
+    &self,
+    query: &str,
+    variables: T,
+    options: RequestOptions,
+  ) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    let summary = document::summarize(query);
+    let variables = match upload::serialize(&variables)? {
+      Variables::Json(variables) => variables,
+      Variables::Uploads(variables, uploads) => {
+        // https://github.com/jaydenseric/graphql-multipart-request-spec
+        let body = self.request_body(query, &summary, variables, &options)?;
+        let (content_type, body) = upload::multipart(&to_json(&body)?, uploads).await?;
+        let payload = Payload::Multipart { content_type, body };
+        return self
+          .send_payload(&self.config.endpoint, query, summary.has_mutation, payload)
+          .await;
+      }
+    };
+
+    #[cfg(feature = "batching")]
+    if let Some(batcher) = &self.batcher {
+      let batchable = self.method(&summary, &options) == HttpMethod::Post
+        && !summary.has_mutation
+        && options.extensions.is_empty()
+        && self.config.persisted_queries.is_none();
+      if batchable {
+        let request = GraphQLRequest {
+          operation_name: options.operation_name,
+          ..GraphQLRequest::new(query).with_variables(variables)?
+        };
+        return self.query_batched(batcher, request, summary).await;
+      }
+    }
+    self
+      .query_with_vars_by_endpoint(&self.config.endpoint, query, &summary, variables, &options)
+      .await
+  }
+
+  /// Send several operations in one request, as a json array, and get their results in the same order.
+  /// GraphQL errors are returned in the result of their operation. Nothing is sent without operations.
+  pub async fn batch(
+    &self,
+    requests: Vec<GraphQLRequest>,
+  ) -> Result<Vec<GraphQLResponse<Value>>, GraphQLError> {
+    if requests.is_empty() {
+      return Ok(Vec::new());
+    }
+    let requests = requests
+      .into_iter()
+      .map(|request| {
+        let summary = document::summarize(&request.query);
+        (request, summary)
+      })
+      .collect();
+    self.send_requests(requests).await
+  }
+
+  /// Send operations whose documents are already scanned in one request
+  async fn send_requests(
+    &self,
+    requests: Vec<(GraphQLRequest, Summary)>,
+  ) -> Result<Vec<GraphQLResponse<Value>>, GraphQLError> {
+    let mut bodies = Vec::with_capacity(requests.len());
+    let mut is_mutation = false;
+    for (request, summary) in requests {
+      is_mutation |= summary.has_mutation;
+      let options = RequestOptions {
+        operation_name: request.operation_name,
+        ..Default::default()
+      };
+      bodies.push(self.request_body(&request.query, &summary, request.variables, &options)?);
+    }
+
+    let payload = Payload::Json(to_json(&bodies)?);
+    let raw_response = self
+      .send_raw(&self.config.endpoint, payload, is_mutation)
+      .await?;
+
+    // servers without batching support answer with a single result, usually an error
+    if !raw_response.is_success() || !raw_response.body.trim_start().starts_with('[') {
+      let json: GraphQLResponse<Value> = parse_response(&raw_response)?;
+      let kind = if raw_response.is_success() {
+        GraphQLErrorKind::GraphQL
+      } else {
+        GraphQLErrorKind::HttpStatus(raw_response.status)
+      };
+      return Err(
+        GraphQLError::with_message_and_json(
+          format!("The batch is rejected [{}]", raw_response.status),
+          json.errors.unwrap_or_default(),
+        )
+        .of_kind(kind),
+      );
+    }
+
+    let results: Vec<GraphQLResponse<Value>> = parse_response(&raw_response)?;
+    if results.len() != bodies.len() {
+      return Err(GraphQLError::with_kind(
+        GraphQLErrorKind::Deserialize,
+        format!(
+          "Expected {} results, the response has {}",
+          bodies.len(),
+          results.len()
+        ),
+      ));
+    }
+    Ok(results)
+  }
+
+  /// Subscribe over WebSocket, using the `graphql-transport-ws` or the legacy `graphql-ws` protocol.
+  /// The `http(s)` scheme of the endpoint is replaced with `ws(s)`. The server must answer the
+  /// handshake with the subprotocol it speaks, servers which do not fail with a protocol error.
+  #[cfg(all(feature = "ws", not(target_arch = "wasm32")))]
+  pub async fn subscribe<K>(&self, query: &str) -> Result<SubscriptionStream<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de> + Send + 'static,
+  {
+    self.subscribe_with_vars::<K, ()>(query, ()).await
+  }
+
+  #[cfg(all(feature = "ws", not(target_arch = "wasm32")))]
+  pub async fn subscribe_with_vars<K, T: Serialize>(
+    &self,
+    query: &str,
+    variables: T,
+  ) -> Result<SubscriptionStream<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de> + Send + 'static,
+  {
+    let body = self.request_body(
+      query,
+      &document::summarize(query),
+      variables,
+      &RequestOptions::default(),
+    )?;
+    crate::ws::subscribe(&self.config, body).await
+  }
+
+  /// Subscribe using the GraphQL over Server-Sent Events protocol, in distinct connections mode
+  pub async fn subscribe_sse<K>(&self, query: &str) -> Result<SubscriptionStream<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de> + Send + 'static,
+  {
+    self.subscribe_sse_with_vars::<K, ()>(query, ()).await
+  }
+
+  pub async fn subscribe_sse_with_vars<K, T: Serialize>(
+    &self,
+    query: &str,
+    variables: T,
+  ) -> Result<SubscriptionStream<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de> + Send + 'static,
+  {
+    let body = to_json(&self.request_body(
+      query,
+      &document::summarize(query),
+      variables,
+      &RequestOptions::default(),
+    )?)?;
+    let mut request = self.request(&self.config.endpoint, &Payload::Json(body))?;
+    request
+      .headers
+      .insert("accept".to_string(), "text/event-stream".to_string());
+
+    let response = self.send_streaming(request).await?;
+    if !response.is_success() {
+      let status = response.status;
+      let text = response.text().await?;
+      return Err(GraphQLError::with_kind(
+        GraphQLErrorKind::HttpStatus(status),
+        format!("The response is [{}]: {}", status, text),
+      ));
+    }
+    Ok(crate::sse::subscription(response.body))
+  }
+
+  /// Run a query which uses `@defer` or `@stream`, payloads are streamed as the server sends them.
+  /// Use [`IncrementalResult`](crate::IncrementalResult) to merge them into the complete response.
+  pub async fn query_incremental<T: Serialize>(
+    &self,
+    query: &str,
+    variables: T,
+  ) -> Result<IncrementalStream, GraphQLError> {
+    let body = to_json(&self.request_body(
+      query,
+      &document::summarize(query),
+      variables,
+      &RequestOptions::default(),
+    )?)?;
+    let mut request = self.request(&self.config.endpoint, &Payload::Json(body))?;
+    request.headers.insert(
+      "accept".to_string(),
+      "multipart/mixed; deferSpec=20220824, application/json".to_string(),
+    );
+
+    let response = self.send_streaming(request).await?;
+    if !response.is_success() {
+      let status = response.status;
+      let text = response.text().await?;
+      return Err(GraphQLError::with_kind(
+        GraphQLErrorKind::HttpStatus(status),
+        format!("The response is [{}]: {}", status, text),
+      ));
+    }
+    crate::incremental::stream(response).await
+  }
+
+  fn method(&self, summary: &Summary, options: &RequestOptions) -> HttpMethod {
+    match options.method.or(self.config.query_method) {
+      // mutations are never sent with GET, GET requests must not have side effects
+      Some(HttpMethod::Get) if !summary.has_mutation => HttpMethod::Get,
+      _ => HttpMethod::Post,
+    }
+  }
+
+  /// Queue the operation in the current batch and wait for its result
+  #[cfg(feature = "batching")]
+  async fn query_batched<K>(
+    &self,
+    batcher: &Arc<Batcher>,
+    request: GraphQLRequest,
+    summary: Summary,
+  ) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    let (receiver, batch) = batcher.push(request, summary);
+    if let Some(batch) = batch {
+      let client = self.clone();
+      let send = async move {
+        let entries = batch.entries().await;
+        client.send_batch(entries).await;
+      };
+      // on a task of its own, so the batch is sent and its results are dispatched even if
+      // the callers waiting for them are dropped. Without a runtime it is sent by this caller.
+      if let Err(send) = runtime::spawn(send) {
+        send.await;
+      }
+    }
+
+    match receiver.await {
+      Ok(result) => result?.into_typed(),
+      // the operation may have been sent already, it must not be sent a second time
+      Err(_) => Err(GraphQLError::with_kind(
+        GraphQLErrorKind::Transport,
+        "The batch was dropped before its results were received",
+      )),
+    }
+  }
+
+  /// Send a batch and dispatch the results to the callers, a single operation is sent alone
+  #[cfg(feature = "batching")]
+  async fn send_batch(&self, mut entries: Vec<Entry>) {
+    if entries.is_empty() {
+      return;
+    }
+    if entries.len() == 1 {
+      let Entry {
+        request,
+        summary,
+        sender,
+      } = entries.remove(0);
+      let options = RequestOptions {
+        operation_name: request.operation_name,
+        ..Default::default()
+      };
+      let result = self
+        .query_with_vars_by_endpoint(
+          &self.config.endpoint,
+          &request.query,
+          &summary,
+          request.variables,
+          &options,
+        )
+        .await;
+      let _ = sender.send(result);
+      return;
+    }
+
+    let (requests, senders): (Vec<_>, Vec<_>) = entries
+      .into_iter()
+      .map(|entry| ((entry.request, entry.summary), entry.sender))
+      .unzip();
+    match self.send_requests(requests).await {
+      Ok(results) => {
+        for (sender, result) in senders.into_iter().zip(results) {
+          let _ = sender.send(Ok(result));
+        }
+      }
+      Err(e) => {
+        for sender in senders {
+          let _ = sender.send(Err(e.clone()));
+        }
+      }
+    }
+  }
+
+  /// Request body of an operation, sent by id when trusted documents are configured
+  fn request_body<T: Serialize>(
+    &self,
+    query: &str,
+    summary: &Summary,
+    variables: T,
+    options: &RequestOptions,
+  ) -> Result<RequestBody<T>, GraphQLError> {
+    let mut body = RequestBody::new(query, variables);
+    // the name of the only operation, so servers can trace it
+    body.operation_name = options
+      .operation_name
+      .clone()
+      .or_else(|| summary.operation_name.clone());
+    if !options.extensions.is_empty() {
+      body.extensions = Some(options.extensions.clone());
+    }
+
+    let documents = match &self.config.persisted_documents {
+      Some(documents) => documents,
+      None => return Ok(body),
+    };
+    let id = documents.id_of(query).ok_or_else(|| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Config,
+        "The operation is not in the persisted documents manifest",
+      )
+      .with_query(query)
+    })?;
+
+    body.query = None;
+    match documents.id_field {
+      DocumentIdField::DocumentId => body.document_id = Some(id.to_string()),
+      DocumentIdField::Id => body.id = Some(id.to_string()),
+    }
+    Ok(body)
+  }
+
+  /// Build a request to the endpoint, with the configured headers
+  fn request(&self, endpoint: &str, payload: &Payload) -> Result<TransportRequest, GraphQLError> {
+    let mut request = match payload {
+      Payload::Json(body) => {
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "application/json".to_string());
+        TransportRequest {
+          method: HttpMethod::Post,
+          url: endpoint.to_string(),
+          headers,
+          body: body.clone(),
+        }
+      }
+      Payload::Multipart { content_type, body } => {
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), content_type.clone());
+        // servers with CSRF prevention, like Apollo Server, reject multipart requests without it
+        headers.insert("apollo-require-preflight".to_string(), "true".to_string());
+        TransportRequest {
+          method: HttpMethod::Post,
+          url: endpoint.to_string(),
+          headers,
+          body: body.clone(),
+        }
+      }
+      Payload::Params(params) => {
+        let mut url = Url::from_str(endpoint).map_err(|e| {
+          GraphQLError::with_kind(
+            GraphQLErrorKind::Config,
+            format!("Wrong endpoint: {}. {:?}", endpoint, e),
+          )
+          .with_source(e)
+        })?;
+        url.query_pairs_mut().extend_pairs(params);
+        TransportRequest {
+          method: HttpMethod::Get,
+          url: url.to_string(),
+          headers: HashMap::new(),
+          body: Vec::new(),
+        }
+      }
+    };
+    if let Some(headers) = &self.config.headers {
+      if !headers.is_empty() {
+        for (name, value) in headers {
+          request.headers.insert(name.to_lowercase(), value.clone());
+        }
+      }
+    }
+    Ok(request)
+  }
+
+  /// Send a request through the middleware and the transport
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    Next::new(&*self.transport, &self.middleware)
+      .run(request)
+      .await
+  }
+
+  async fn send_streaming(
+    &self,
+    request: TransportRequest,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    NextStreaming::new(&*self.transport, &self.middleware)
+      .run(request)
       .await
   }
 
@@ -131,52 +798,138 @@ impl GQLClient {
     &self,
     endpoint: impl AsRef<str>,
     query: &str,
+    summary: &Summary,
     variables: T,
-  ) -> Result<Option<K>, GraphQLError>
+    options: &RequestOptions,
+  ) -> Result<GraphQLResponse<K>, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    let endpoint = endpoint.as_ref();
+    let is_mutation = summary.has_mutation;
+    let method = self.method(summary, options);
+
+    let mut body = self.request_body(query, summary, variables, options)?;
+    let persisted_queries = match &self.config.persisted_queries {
+      // trusted documents are already sent by id
+      Some(persisted_queries) if body.query.is_some() => persisted_queries,
+      _ => {
+        let payload = Payload::new(&body, method)?;
+        return self
+          .send_payload(endpoint, query, is_mutation, payload)
+          .await;
+      }
+    };
+
+    // https://www.apollographql.com/docs/apollo-server/performance/apq
+    body.query = None;
+    body.extensions.get_or_insert_with(Map::new).insert(
+      "persistedQuery".to_string(),
+      serde_json::json!({ "version": 1, "sha256Hash": sha256(query) }),
+    );
+    let payload = if persisted_queries.use_get && !is_mutation {
+      Payload::params(&body)?
+    } else {
+      Payload::new(&body, method)?
+    };
+    match self
+      .send_payload(endpoint, query, is_mutation, payload)
+      .await
+    {
+      Ok(response) if !persisted_query_not_found(response.errors()) => return Ok(response),
+      Err(e) if !persisted_query_not_found(e.errors()) => return Err(e),
+      _ => {}
+    }
+
+    // the server does not know the hash yet, it is stored along with the full query
+    body.query = Some(query.to_string());
+    let payload = Payload::new(&body, method)?;
+    self
+      .send_payload(endpoint, query, is_mutation, payload)
+      .await
+  }
+
+  /// Send a payload and parse the response
+  async fn send_payload<K>(
+    &self,
+    endpoint: &str,
+    query: &str,
+    is_mutation: bool,
+    payload: Payload,
+  ) -> Result<GraphQLResponse<K>, GraphQLError>
   where
     K: for<'de> Deserialize<'de>,
   {
+    let raw_response = self.send_raw(endpoint, payload, is_mutation).await?;
+    let json: GraphQLResponse<K> = parse_response(&raw_response)?;
+
+    if !raw_response.is_success() {
+      return Err(
+        GraphQLError::with_message_and_json(
+          format!("The response is [{}]", raw_response.status),
+          json.errors.unwrap_or_default(),
+        )
+        .of_kind(GraphQLErrorKind::HttpStatus(raw_response.status))
+        .with_query(query),
+      );
+    }
+
+    if json.errors.is_none() && json.data.is_none() {
+      log::warn!(target: "gql-client", "The deserialized data is none, the response is: {}", raw_response.body);
+    }
+
+    Ok(json)
+  }
+
+  /// Send a payload, following redirects and retrying as configured
+  async fn send_raw(
+    &self,
+    endpoint: &str,
+    payload: Payload,
+    is_mutation: bool,
+  ) -> Result<TransportResponse, GraphQLError> {
     let mut times = 1;
-    let mut endpoint = endpoint.as_ref().to_string();
-    let endpoint_url = Url::from_str(&endpoint)
-      .map_err(|e| GraphQLError::with_text(format!("Wrong endpoint: {}. {:?}", endpoint, e)))?;
+    let mut endpoint = endpoint.to_string();
+    let endpoint_url = Url::from_str(&endpoint).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Config,
+        format!("Wrong endpoint: {}. {:?}", endpoint, e),
+      )
+      .with_source(e)
+    })?;
     let schema = endpoint_url.scheme();
-    let host = endpoint_url
-      .host()
-      .ok_or_else(|| GraphQLError::with_text(format!("Wrong endpoint: {}", endpoint)))?;
+    let host = endpoint_url.host().ok_or_else(|| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Config,
+        format!("Wrong endpoint: {}", endpoint),
+      )
+    })?;
 
-    let client: Client = self.client()?;
-    let body = RequestBody {
-      query: query.to_string(),
-      variables,
-    };
+    let retry = self
+      .config
+      .retry
+      .as_ref()
+      .filter(|retry| retry.retry_mutations || !is_mutation);
+    let mut attempt = 1;
 
     loop {
       if times > 10 {
-        return Err(GraphQLError::with_text(format!(
-          "Many redirect location: {}",
-          endpoint
-        )));
+        return Err(GraphQLError::with_kind(
+          GraphQLErrorKind::Redirect,
+          format!("Many redirect location: {}", endpoint),
+        ));
       }
 
-      let mut request = client.post(&endpoint).json(&body);
-      if let Some(headers) = &self.config.headers {
-        if !headers.is_empty() {
-          for (name, value) in headers {
-            request = request.header(name, value);
-          }
-        }
+      let request = self.request(&endpoint, &payload)?;
+      let result = self.send(request).await;
+      if let Some(delay) = retry.and_then(|retry| retry.delay(attempt, &result)) {
+        log::debug!(target: "gql-client", "Retrying in {:?}, attempt {} failed", delay, attempt);
+        runtime::sleep(delay).await;
+        attempt += 1;
+        continue;
       }
-
-      let raw_response = request.send().await?;
-      if let Some(location) = raw_response.headers().get(reqwest::header::LOCATION) {
-        let redirect_url = location.to_str().map_err(|e| {
-          GraphQLError::with_text(format!(
-            "Failed to parse response header: Location. {:?}",
-            e
-          ))
-        })?;
-
+      let raw_response = result?;
+      if let Some(redirect_url) = raw_response.header("location") {
         // if the response location start with http:// or https://
         if redirect_url.starts_with("http://") || redirect_url.starts_with("https://") {
           times += 1;
@@ -194,35 +947,61 @@ impl GQLClient {
         continue;
       }
 
-      let status = raw_response.status();
-      let response_body_text = raw_response
-        .text()
-        .await
-        .map_err(|e| GraphQLError::with_text(format!("Can not get response: {:?}", e)))?;
-
-      let json: GraphQLResponse<K> = serde_json::from_str(&response_body_text).map_err(|e| {
-        GraphQLError::with_text(format!(
-          "Failed to parse response: {:?}. The response body is: {}",
-          e, response_body_text
-        ))
-      })?;
-
-      if !status.is_success() {
-        return Err(GraphQLError::with_message_and_json(
-          format!("The response is [{}]", status.as_u16()),
-          json.errors.unwrap_or_default(),
-        ));
-      }
-
-      // Check if error messages have been received
-      if json.errors.is_some() {
-        return Err(GraphQLError::with_json(json.errors.unwrap_or_default()));
-      }
-      if json.data.is_none() {
-        log::warn!(target: "gql-client", "The deserialized data is none, the response is: {}", response_body_text);
-      }
-
-      return Ok(json.data);
+      return Ok(raw_response);
     }
   }
 }
+
+/// Deserialize a response body, the kind of the error depends on the status
+fn parse_response<R>(raw_response: &TransportResponse) -> Result<R, GraphQLError>
+where
+  R: for<'de> Deserialize<'de>,
+{
+  serde_json::from_str(&raw_response.body).map_err(|e| {
+    // error pages of proxies and gateways are usually not json
+    let kind = if raw_response.is_success() {
+      GraphQLErrorKind::Deserialize
+    } else {
+      GraphQLErrorKind::HttpStatus(raw_response.status)
+    };
+    GraphQLError::with_kind(
+      kind,
+      format!(
+        "Failed to parse response: {:?}. The response body is: {}",
+        e, raw_response.body
+      ),
+    )
+    .with_source(e)
+  })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, GraphQLError> {
+  serde_json::to_vec(value).map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Serialize,
+      format!("Failed to serialize request: {:?}", e),
+    )
+    .with_source(e)
+  })
+}
+
+/// Hex encoded sha256 hash of the query, identifying it as a persisted query
+fn sha256(query: &str) -> String {
+  Sha256::digest(query.as_bytes())
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+/// Whether the server asks for the full query, it does not know the hash or does not support persisted queries
+fn persisted_query_not_found(errors: &[GraphQLErrorMessage]) -> bool {
+  errors.iter().any(|error| {
+    matches!(
+      error.message(),
+      "PersistedQueryNotFound" | "PersistedQueryNotSupported"
+    ) || matches!(
+      error.extension_code(),
+      Some("PERSISTED_QUERY_NOT_FOUND") | Some("PERSISTED_QUERY_NOT_SUPPORTED")
+    )
+  })
+}
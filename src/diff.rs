@@ -0,0 +1,665 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::introspection::{Directive, Field, InputValue, Schema, SchemaType, TypeKind, TypeRef};
+use crate::sdl::BUILTIN_DIRECTIVES;
+
+/// How a change affects the clients of a schema
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criticality {
+  /// operations valid against the old schema may fail against the new one
+  Breaking,
+  /// operations keep working, but clients may not handle the new values or defaults
+  Dangerous,
+  /// operations keep working as before
+  Safe,
+}
+
+impl fmt::Display for Criticality {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Criticality::Breaking => "breaking",
+      Criticality::Dangerous => "dangerous",
+      Criticality::Safe => "safe",
+    })
+  }
+}
+
+/// What changed between two schemas
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+  RootTypeChanged,
+  TypeAdded,
+  TypeRemoved,
+  TypeKindChanged,
+  FieldAdded,
+  FieldRemoved,
+  FieldTypeChanged,
+  FieldDeprecationChanged,
+  ArgumentAdded,
+  ArgumentRemoved,
+  ArgumentTypeChanged,
+  ArgumentDefaultChanged,
+  InputFieldAdded,
+  InputFieldRemoved,
+  InputFieldTypeChanged,
+  InputFieldDefaultChanged,
+  EnumValueAdded,
+  EnumValueRemoved,
+  EnumValueDeprecationChanged,
+  UnionMemberAdded,
+  UnionMemberRemoved,
+  InterfaceAdded,
+  InterfaceRemoved,
+  DirectiveAdded,
+  DirectiveRemoved,
+  DirectiveLocationAdded,
+  DirectiveLocationRemoved,
+  DirectiveRepeatableChanged,
+}
+
+/// Change between two schemas
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SchemaChange {
+  pub kind: ChangeKind,
+  pub criticality: Criticality,
+  /// schema coordinate of the changed element, like `User.name`, `Query.users(first:)` or `@cache`
+  pub path: String,
+  pub message: String,
+}
+
+impl fmt::Display for SchemaChange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "[{}] {}", self.criticality, self.message)
+  }
+}
+
+/// Changes between two schemas, see [`Schema::diff`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SchemaDiff {
+  pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+  pub fn is_empty(&self) -> bool {
+    self.changes.is_empty()
+  }
+
+  pub fn has_breaking_changes(&self) -> bool {
+    self.breaking().next().is_some()
+  }
+
+  pub fn breaking(&self) -> impl Iterator<Item = &SchemaChange> {
+    self.with_criticality(Criticality::Breaking)
+  }
+
+  pub fn dangerous(&self) -> impl Iterator<Item = &SchemaChange> {
+    self.with_criticality(Criticality::Dangerous)
+  }
+
+  pub fn safe(&self) -> impl Iterator<Item = &SchemaChange> {
+    self.with_criticality(Criticality::Safe)
+  }
+
+  fn with_criticality(&self, criticality: Criticality) -> impl Iterator<Item = &SchemaChange> {
+    self
+      .changes
+      .iter()
+      .filter(move |change| change.criticality == criticality)
+  }
+
+  fn push(
+    &mut self,
+    kind: ChangeKind,
+    criticality: Criticality,
+    path: impl Into<String>,
+    message: String,
+  ) {
+    self.changes.push(SchemaChange {
+      kind,
+      criticality,
+      path: path.into(),
+      message,
+    });
+  }
+}
+
+/// One change per line
+impl fmt::Display for SchemaDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for change in &self.changes {
+      writeln!(f, "{}", change)?;
+    }
+    Ok(())
+  }
+}
+
+impl Schema {
+  /// Changes from this schema to a new one, like production to staging.
+  /// Built-in scalars and directives, introspection types and descriptions are not compared.
+  pub fn diff(&self, new: &Schema) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+
+    let roots = [
+      ("query", &self.query_type, &new.query_type),
+      ("mutation", &self.mutation_type, &new.mutation_type),
+      (
+        "subscription",
+        &self.subscription_type,
+        &new.subscription_type,
+      ),
+    ];
+    for (operation, old, new) in roots {
+      if old == new {
+        continue;
+      }
+      let criticality = match old {
+        Some(_) => Criticality::Breaking,
+        None => Criticality::Safe,
+      };
+      let name = |name: &Option<String>| name.clone().unwrap_or_else(|| "none".to_string());
+      diff.push(
+        ChangeKind::RootTypeChanged,
+        criticality,
+        operation,
+        format!(
+          "Root {} type changed from {} to {}",
+          operation,
+          name(old),
+          name(new)
+        ),
+      );
+    }
+
+    let new_types: Vec<_> = user_types(new).collect();
+    for old_ty in user_types(self) {
+      match new_types.iter().find(|ty| ty.name == old_ty.name) {
+        Some(new_ty) => diff_type(&mut diff, old_ty, new_ty),
+        None => diff.push(
+          ChangeKind::TypeRemoved,
+          Criticality::Breaking,
+          &old_ty.name,
+          format!("Type {} was removed", old_ty.name),
+        ),
+      }
+    }
+    for new_ty in &new_types {
+      if self.get_type(&new_ty.name).is_none() {
+        diff.push(
+          ChangeKind::TypeAdded,
+          Criticality::Safe,
+          &new_ty.name,
+          format!("Type {} was added", new_ty.name),
+        );
+      }
+    }
+
+    let new_directives: Vec<_> = user_directives(new).collect();
+    for old_directive in user_directives(self) {
+      let path = format!("@{}", old_directive.name);
+      match new_directives
+        .iter()
+        .find(|directive| directive.name == old_directive.name)
+      {
+        Some(new_directive) => diff_directive(&mut diff, &path, old_directive, new_directive),
+        None => diff.push(
+          ChangeKind::DirectiveRemoved,
+          Criticality::Breaking,
+          &path,
+          format!("Directive {} was removed", path),
+        ),
+      }
+    }
+    for new_directive in new_directives {
+      if self.get_directive(&new_directive.name).is_none() {
+        let path = format!("@{}", new_directive.name);
+        let message = format!("Directive {} was added", path);
+        diff.push(ChangeKind::DirectiveAdded, Criticality::Safe, path, message);
+      }
+    }
+
+    diff
+  }
+}
+
+/// Types defined by the schema, without the built-in scalars and the introspection types
+fn user_types(schema: &Schema) -> impl Iterator<Item = &SchemaType> {
+  schema
+    .types
+    .iter()
+    .filter(|ty| !ty.is_introspection() && !ty.is_builtin_scalar())
+}
+
+fn user_directives(schema: &Schema) -> impl Iterator<Item = &Directive> {
+  schema
+    .directives
+    .iter()
+    .filter(|directive| !BUILTIN_DIRECTIVES.contains(&directive.name.as_str()))
+}
+
+fn diff_type(diff: &mut SchemaDiff, old: &SchemaType, new: &SchemaType) {
+  if old.kind != new.kind {
+    diff.push(
+      ChangeKind::TypeKindChanged,
+      Criticality::Breaking,
+      &old.name,
+      format!(
+        "Type {} changed from {} to {}",
+        old.name,
+        kind_name(old.kind),
+        kind_name(new.kind)
+      ),
+    );
+    return;
+  }
+
+  match old.kind {
+    TypeKind::Object | TypeKind::Interface => {
+      diff_fields(diff, old, new);
+      diff_members(
+        diff,
+        &old.name,
+        &old.interfaces,
+        &new.interfaces,
+        (ChangeKind::InterfaceAdded, ChangeKind::InterfaceRemoved),
+        "interfaces",
+      );
+    }
+    TypeKind::InputObject => {
+      diff_input_values(
+        diff,
+        &old.name,
+        &old.input_fields,
+        &new.input_fields,
+        InputKind::Field,
+      );
+    }
+    TypeKind::Union => diff_members(
+      diff,
+      &old.name,
+      &old.possible_types,
+      &new.possible_types,
+      (ChangeKind::UnionMemberAdded, ChangeKind::UnionMemberRemoved),
+      "members",
+    ),
+    TypeKind::Enum => diff_enum_values(diff, old, new),
+    TypeKind::Scalar | TypeKind::List | TypeKind::NonNull => {}
+  }
+}
+
+fn diff_fields(diff: &mut SchemaDiff, old: &SchemaType, new: &SchemaType) {
+  for old_field in &old.fields {
+    let path = format!("{}.{}", old.name, old_field.name);
+    let new_field = match new.get_field(&old_field.name) {
+      Some(new_field) => new_field,
+      None => {
+        let message = format!("Field {} was removed", path);
+        diff.push(
+          ChangeKind::FieldRemoved,
+          Criticality::Breaking,
+          path,
+          message,
+        );
+        continue;
+      }
+    };
+
+    if old_field.ty.to_string() != new_field.ty.to_string() {
+      let criticality = if is_safe_output_change(&old_field.ty, &new_field.ty) {
+        Criticality::Safe
+      } else {
+        Criticality::Breaking
+      };
+      diff.push(
+        ChangeKind::FieldTypeChanged,
+        criticality,
+        &path,
+        format!(
+          "Field {} changed type from {} to {}",
+          path, old_field.ty, new_field.ty
+        ),
+      );
+    }
+    diff_input_values(
+      diff,
+      &path,
+      &old_field.args,
+      &new_field.args,
+      InputKind::Argument,
+    );
+    diff_field_deprecation(diff, &path, old_field, new_field);
+  }
+
+  for new_field in &new.fields {
+    if old.get_field(&new_field.name).is_none() {
+      let path = format!("{}.{}", new.name, new_field.name);
+      let message = format!("Field {} was added", path);
+      diff.push(ChangeKind::FieldAdded, Criticality::Safe, path, message);
+    }
+  }
+}
+
+fn diff_field_deprecation(diff: &mut SchemaDiff, path: &str, old: &Field, new: &Field) {
+  if let Some(message) = deprecation_message(
+    old.is_deprecated,
+    new.is_deprecated,
+    new.deprecation_reason.as_deref(),
+  ) {
+    diff.push(
+      ChangeKind::FieldDeprecationChanged,
+      Criticality::Safe,
+      path,
+      format!("Field {} {}", path, message),
+    );
+  }
+}
+
+fn deprecation_message(old: bool, new: bool, reason: Option<&str>) -> Option<String> {
+  match (old, new) {
+    (false, true) => Some(match reason {
+      Some(reason) => format!("was deprecated: {}", reason),
+      None => "was deprecated".to_string(),
+    }),
+    (true, false) => Some("is no longer deprecated".to_string()),
+    _ => None,
+  }
+}
+
+#[derive(Clone, Copy)]
+enum InputKind {
+  Argument,
+  Field,
+}
+
+/// Arguments of a field or directive, or fields of an input object
+fn diff_input_values(
+  diff: &mut SchemaDiff,
+  parent: &str,
+  old: &[InputValue],
+  new: &[InputValue],
+  input_kind: InputKind,
+) {
+  let (label, added, removed, type_changed, default_changed) = match input_kind {
+    InputKind::Argument => (
+      "Argument",
+      ChangeKind::ArgumentAdded,
+      ChangeKind::ArgumentRemoved,
+      ChangeKind::ArgumentTypeChanged,
+      ChangeKind::ArgumentDefaultChanged,
+    ),
+    InputKind::Field => (
+      "Input field",
+      ChangeKind::InputFieldAdded,
+      ChangeKind::InputFieldRemoved,
+      ChangeKind::InputFieldTypeChanged,
+      ChangeKind::InputFieldDefaultChanged,
+    ),
+  };
+  let path = |value: &InputValue| match input_kind {
+    InputKind::Argument => format!("{}({}:)", parent, value.name),
+    InputKind::Field => format!("{}.{}", parent, value.name),
+  };
+
+  for old_value in old {
+    let path = path(old_value);
+    let new_value = match new.iter().find(|value| value.name == old_value.name) {
+      Some(new_value) => new_value,
+      None => {
+        let message = format!("{} {} was removed", label, path);
+        diff.push(removed, Criticality::Breaking, path, message);
+        continue;
+      }
+    };
+
+    if old_value.ty.to_string() != new_value.ty.to_string() {
+      let criticality = if is_safe_input_change(&old_value.ty, &new_value.ty) {
+        Criticality::Safe
+      } else {
+        Criticality::Breaking
+      };
+      diff.push(
+        type_changed,
+        criticality,
+        &path,
+        format!(
+          "{} {} changed type from {} to {}",
+          label, path, old_value.ty, new_value.ty
+        ),
+      );
+    }
+    if old_value.default_value != new_value.default_value {
+      let default = |value: &InputValue| {
+        value
+          .default_value
+          .clone()
+          .unwrap_or_else(|| "none".to_string())
+      };
+      diff.push(
+        default_changed,
+        Criticality::Dangerous,
+        &path,
+        format!(
+          "{} {} changed default value from {} to {}",
+          label,
+          path,
+          default(old_value),
+          default(new_value)
+        ),
+      );
+    }
+  }
+
+  for new_value in new {
+    if old.iter().any(|value| value.name == new_value.name) {
+      continue;
+    }
+    let path = path(new_value);
+    let (criticality, message) = if is_required(new_value) {
+      (
+        Criticality::Breaking,
+        format!("Required {} {} was added", label.to_lowercase(), path),
+      )
+    } else {
+      (
+        Criticality::Dangerous,
+        format!("Optional {} {} was added", label.to_lowercase(), path),
+      )
+    };
+    diff.push(added, criticality, path, message);
+  }
+}
+
+fn is_required(value: &InputValue) -> bool {
+  value.ty.is_non_null() && value.default_value.is_none()
+}
+
+/// Interfaces of an object or interface, or members of a union.
+/// Removing one breaks the fragments on it, adding one may reach clients which do not handle it.
+fn diff_members(
+  diff: &mut SchemaDiff,
+  parent: &str,
+  old: &[TypeRef],
+  new: &[TypeRef],
+  (added, removed): (ChangeKind, ChangeKind),
+  label: &str,
+) {
+  let contains = |members: &[TypeRef], member: &TypeRef| {
+    members
+      .iter()
+      .any(|other| other.named_type() == member.named_type())
+  };
+  for member in old.iter().filter(|member| !contains(new, member)) {
+    diff.push(
+      removed,
+      Criticality::Breaking,
+      parent,
+      format!(
+        "{} was removed from the {} of {}",
+        member.named_type(),
+        label,
+        parent
+      ),
+    );
+  }
+  for member in new.iter().filter(|member| !contains(old, member)) {
+    diff.push(
+      added,
+      Criticality::Dangerous,
+      parent,
+      format!(
+        "{} was added to the {} of {}",
+        member.named_type(),
+        label,
+        parent
+      ),
+    );
+  }
+}
+
+fn diff_enum_values(diff: &mut SchemaDiff, old: &SchemaType, new: &SchemaType) {
+  for old_value in &old.enum_values {
+    let path = format!("{}.{}", old.name, old_value.name);
+    let new_value = new
+      .enum_values
+      .iter()
+      .find(|value| value.name == old_value.name);
+    let new_value = match new_value {
+      Some(new_value) => new_value,
+      None => {
+        let message = format!("Enum value {} was removed", path);
+        diff.push(
+          ChangeKind::EnumValueRemoved,
+          Criticality::Breaking,
+          path,
+          message,
+        );
+        continue;
+      }
+    };
+    if let Some(message) = deprecation_message(
+      old_value.is_deprecated,
+      new_value.is_deprecated,
+      new_value.deprecation_reason.as_deref(),
+    ) {
+      let message = format!("Enum value {} {}", path, message);
+      diff.push(
+        ChangeKind::EnumValueDeprecationChanged,
+        Criticality::Safe,
+        path,
+        message,
+      );
+    }
+  }
+
+  for new_value in &new.enum_values {
+    if old
+      .enum_values
+      .iter()
+      .any(|value| value.name == new_value.name)
+    {
+      continue;
+    }
+    let path = format!("{}.{}", new.name, new_value.name);
+    let message = format!("Enum value {} was added", path);
+    diff.push(
+      ChangeKind::EnumValueAdded,
+      Criticality::Dangerous,
+      path,
+      message,
+    );
+  }
+}
+
+fn diff_directive(diff: &mut SchemaDiff, path: &str, old: &Directive, new: &Directive) {
+  diff_input_values(diff, path, &old.args, &new.args, InputKind::Argument);
+  for location in old
+    .locations
+    .iter()
+    .filter(|location| !new.locations.contains(location))
+  {
+    diff.push(
+      ChangeKind::DirectiveLocationRemoved,
+      Criticality::Breaking,
+      path,
+      format!("Location {} was removed from directive {}", location, path),
+    );
+  }
+  for location in new
+    .locations
+    .iter()
+    .filter(|location| !old.locations.contains(location))
+  {
+    diff.push(
+      ChangeKind::DirectiveLocationAdded,
+      Criticality::Safe,
+      path,
+      format!("Location {} was added to directive {}", location, path),
+    );
+  }
+  if old.is_repeatable != new.is_repeatable {
+    // a directive used several times at the same location becomes invalid
+    let (criticality, message) = if new.is_repeatable {
+      (
+        Criticality::Safe,
+        format!("Directive {} became repeatable", path),
+      )
+    } else {
+      (
+        Criticality::Breaking,
+        format!("Directive {} is no longer repeatable", path),
+      )
+    };
+    diff.push(
+      ChangeKind::DirectiveRepeatableChanged,
+      criticality,
+      path,
+      message,
+    );
+  }
+}
+
+/// Whether a field can return the new type to clients expecting the old one
+fn is_safe_output_change(old: &TypeRef, new: &TypeRef) -> bool {
+  match (old.of_type.as_deref(), new.of_type.as_deref()) {
+    // a nullable field can become non null
+    (_, Some(new_of_type)) if new.is_non_null() && !old.is_non_null() => {
+      is_safe_output_change(old, new_of_type)
+    }
+    (Some(old_of_type), Some(new_of_type)) => {
+      old.kind == new.kind && is_safe_output_change(old_of_type, new_of_type)
+    }
+    (None, None) => old.named_type() == new.named_type(),
+    _ => false,
+  }
+}
+
+/// Whether an argument or input field accepts the values of the old type
+fn is_safe_input_change(old: &TypeRef, new: &TypeRef) -> bool {
+  match (old.of_type.as_deref(), new.of_type.as_deref()) {
+    // a non null input can become nullable
+    (Some(old_of_type), _) if old.is_non_null() && !new.is_non_null() => {
+      is_safe_input_change(old_of_type, new)
+    }
+    (Some(old_of_type), Some(new_of_type)) => {
+      old.kind == new.kind && is_safe_input_change(old_of_type, new_of_type)
+    }
+    (None, None) => old.named_type() == new.named_type(),
+    _ => false,
+  }
+}
+
+fn kind_name(kind: TypeKind) -> &'static str {
+  match kind {
+    TypeKind::Scalar => "scalar",
+    TypeKind::Object => "object",
+    TypeKind::Interface => "interface",
+    TypeKind::Union => "union",
+    TypeKind::Enum => "enum",
+    TypeKind::InputObject => "input object",
+    TypeKind::List => "list",
+    TypeKind::NonNull => "non null",
+  }
+}
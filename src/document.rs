@@ -0,0 +1,102 @@
+use crate::lexer::{self, Token};
+
+/// Type of a GraphQL operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OperationType {
+  Query,
+  Mutation,
+  Subscription,
+}
+
+/// Operation defined in a query document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Operation {
+  pub(crate) operation_type: OperationType,
+  pub(crate) name: Option<String>,
+}
+
+/// What a request needs to know of the operations of its document, so the document is scanned
+/// once per request
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Summary {
+  /// the document defines a mutation
+  pub(crate) has_mutation: bool,
+  /// name of the only operation of the document
+  pub(crate) operation_name: Option<String>,
+}
+
+/// Scan a query document
+pub(crate) fn summarize(document: &str) -> Summary {
+  let operations = operations(document);
+  Summary {
+    has_mutation: operations
+      .iter()
+      .any(|operation| operation.operation_type == OperationType::Mutation),
+    operation_name: match operations.as_slice() {
+      [operation] => operation.name.clone(),
+      _ => None,
+    },
+  }
+}
+
+/// Operations of a query document, in order, fragments are skipped.
+/// Invalid tokens are skipped too, the server reports them.
+fn operations(document: &str) -> Vec<Operation> {
+  let mut operations: Vec<Operation> = Vec::new();
+  // nesting of braces, parentheses and brackets, definitions only start at depth 0
+  let mut depth = 0usize;
+  // inside the header of a definition, before its selection set
+  let mut header = false;
+  let mut expects_name = false;
+
+  for (_, token) in lexer::tokens(document).filter_map(Result::ok) {
+    if std::mem::take(&mut expects_name) {
+      if let Token::Name(name) = token {
+        if let Some(operation) = operations.last_mut() {
+          operation.name = Some(name.to_string());
+        }
+        continue;
+      }
+    }
+
+    let operation_type = match token {
+      Token::Punctuator("{") if depth == 0 => {
+        depth += 1;
+        if !std::mem::take(&mut header) {
+          // query shorthand
+          operations.push(Operation {
+            operation_type: OperationType::Query,
+            name: None,
+          });
+        }
+        continue;
+      }
+      Token::Punctuator("{" | "(" | "[") => {
+        depth += 1;
+        continue;
+      }
+      Token::Punctuator("}" | ")" | "]") => {
+        depth = depth.saturating_sub(1);
+        continue;
+      }
+      _ if depth > 0 || header => continue,
+      Token::Name("query") => OperationType::Query,
+      Token::Name("mutation") => OperationType::Mutation,
+      Token::Name("subscription") => OperationType::Subscription,
+      Token::Name("fragment") => {
+        header = true;
+        continue;
+      }
+      _ => continue,
+    };
+
+    operations.push(Operation {
+      operation_type,
+      name: None,
+    });
+    header = true;
+    expects_name = true;
+  }
+
+  operations
+}
@@ -1,39 +1,166 @@
+use std::error::Error as StdError;
 use std::fmt::{self, Formatter};
+use std::sync::Arc;
 
 use reqwest::Error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Map;
 
 #[derive(Clone)]
 pub struct GraphQLError {
+  kind: GraphQLErrorKind,
   message: String,
   json: Option<Vec<GraphQLErrorMessage>>,
+  query: Option<String>,
+  source: Option<Arc<dyn StdError + Send + Sync>>,
+}
+
+/// What went wrong, to branch on without matching error messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum GraphQLErrorKind {
+  /// the request could not be sent or the response could not be received
+  Transport,
+  /// the request timed out
+  Timeout,
+  /// the server answered with a non success status code
+  HttpStatus(u16),
+  /// the response could not be deserialized
+  Deserialize,
+  /// the request could not be serialized
+  Serialize,
+  /// the server returned GraphQL errors
+  GraphQL,
+  /// the server returned neither data nor errors
+  NoData,
+  /// too many redirects
+  Redirect,
+  /// invalid endpoint, proxy or headers
+  Config,
+  /// the server broke the subscription protocol
+  Protocol,
+  /// any other error
+  Other,
+}
+
+impl From<&Error> for GraphQLErrorKind {
+  fn from(error: &Error) -> Self {
+    if error.is_timeout() {
+      GraphQLErrorKind::Timeout
+    } else if error.is_redirect() {
+      GraphQLErrorKind::Redirect
+    } else if error.is_decode() {
+      GraphQLErrorKind::Deserialize
+    } else if error.is_builder() {
+      GraphQLErrorKind::Config
+    } else if let Some(status) = error.status() {
+      GraphQLErrorKind::HttpStatus(status.as_u16())
+    } else {
+      GraphQLErrorKind::Transport
+    }
+  }
 }
 
 // https://spec.graphql.org/June2018/#sec-Errors
+/// A GraphQL error, extensions are an untyped map unless read with `errors_as`
 #[derive(Deserialize, Debug, Clone)]
-#[allow(dead_code)]
-pub struct GraphQLErrorMessage {
+pub struct GraphQLErrorMessage<E = Map<String, serde_json::Value>> {
   message: String,
   locations: Option<Vec<GraphQLErrorLocation>>,
-  extensions: Option<Map<String, serde_json::Value>>,
+  extensions: Option<E>,
   path: Option<Vec<GraphQLErrorPathParam>>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[allow(dead_code)]
+impl<E> GraphQLErrorMessage<E> {
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  /// Locations in the query document the error refers to
+  pub fn locations(&self) -> &[GraphQLErrorLocation] {
+    self.locations.as_deref().unwrap_or_default()
+  }
+
+  pub fn extensions(&self) -> Option<&E> {
+    self.extensions.as_ref()
+  }
+
+  /// Path of the response field the error occurred in
+  pub fn path(&self) -> &[GraphQLErrorPathParam] {
+    self.path.as_deref().unwrap_or_default()
+  }
+}
+
+impl GraphQLErrorMessage {
+  /// Deserialize the extensions into a caller chosen type
+  pub fn extensions_as<E>(&self) -> Result<GraphQLErrorMessage<E>, GraphQLError>
+  where
+    E: for<'de> Deserialize<'de>,
+  {
+    let extensions = match &self.extensions {
+      Some(extensions) => {
+        let extensions = serde_json::Value::Object(extensions.clone());
+        Some(serde_json::from_value(extensions).map_err(|e| {
+          GraphQLError::with_kind(
+            GraphQLErrorKind::Deserialize,
+            format!("Failed to parse error extensions: {:?}", e),
+          )
+          .with_source(e)
+        })?)
+      }
+      None => None,
+    };
+    Ok(GraphQLErrorMessage {
+      message: self.message.clone(),
+      locations: self.locations.clone(),
+      extensions,
+      path: self.path.clone(),
+    })
+  }
+
+  /// Get an entry of the extensions
+  pub fn extension(&self, key: &str) -> Option<&serde_json::Value> {
+    self.extensions.as_ref()?.get(key)
+  }
+
+  /// The `extensions.code` entry, like `UNAUTHENTICATED` or `FORBIDDEN`
+  pub fn extension_code(&self) -> Option<&str> {
+    self.extension("code")?.as_str()
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GraphQLErrorLocation {
-  line: u32,
-  column: u32,
+  pub line: u32,
+  pub column: u32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum GraphQLErrorPathParam {
   String(String),
   Number(u32),
 }
 
+/// Errors as sent in subscription error messages, either a list, a single error or a response
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum GraphQLErrorPayload {
+  Many(Vec<GraphQLErrorMessage>),
+  Response { errors: Vec<GraphQLErrorMessage> },
+  One(GraphQLErrorMessage),
+}
+
+impl From<GraphQLErrorPayload> for GraphQLError {
+  fn from(payload: GraphQLErrorPayload) -> Self {
+    match payload {
+      GraphQLErrorPayload::Many(errors) => GraphQLError::with_json(errors),
+      GraphQLErrorPayload::Response { errors } => GraphQLError::with_json(errors),
+      GraphQLErrorPayload::One(error) => GraphQLError::with_json(vec![error]),
+    }
+  }
+}
+
 impl GraphQLError {
   /// Check if the provided error message is equal to one of the error messages
   pub fn contains_error_message(&self, message: &str) -> bool {
@@ -42,18 +169,35 @@ impl GraphQLError {
     })
   }
 
+  /// Check if one of the error messages has the provided `extensions.code`
+  pub fn contains_extension_code(&self, code: &str) -> bool {
+    self
+      .errors()
+      .iter()
+      .any(|err| err.extension_code() == Some(code))
+  }
 
   pub fn with_text(message: impl AsRef<str>) -> Self {
+    Self::with_kind(GraphQLErrorKind::Other, message)
+  }
+
+  pub fn with_kind(kind: GraphQLErrorKind, message: impl AsRef<str>) -> Self {
     Self {
+      kind,
       message: message.as_ref().to_string(),
       json: None,
+      query: None,
+      source: None,
     }
   }
 
   pub fn with_message_and_json(message: impl AsRef<str>, json: Vec<GraphQLErrorMessage>) -> Self {
     Self {
+      kind: GraphQLErrorKind::GraphQL,
       message: message.as_ref().to_string(),
       json: Some(json),
+      query: None,
+      source: None,
     }
   }
 
@@ -61,13 +205,62 @@ impl GraphQLError {
     Self::with_message_and_json("Look at json field for more details", json)
   }
 
+  pub(crate) fn of_kind(mut self, kind: GraphQLErrorKind) -> Self {
+    self.kind = kind;
+    self
+  }
+
+  /// Keep the underlying error, returned by [`source`](StdError::source)
+  pub fn with_source(mut self, source: impl StdError + Send + Sync + 'static) -> Self {
+    self.source = Some(Arc::new(source));
+    self
+  }
+
+  #[cfg(feature = "tower")]
+  pub(crate) fn with_boxed_source(mut self, source: Box<dyn StdError + Send + Sync>) -> Self {
+    self.source = Some(Arc::from(source));
+    self
+  }
+
+  /// Keep the query text, the error locations are rendered as snippets of it
+  pub fn with_query(mut self, query: impl AsRef<str>) -> Self {
+    self.query = Some(query.as_ref().to_string());
+    self
+  }
+
+  pub fn kind(&self) -> GraphQLErrorKind {
+    self.kind
+  }
+
   pub fn message(&self) -> &str {
     &self.message
   }
 
+  /// Query the error occurred in, when known
+  pub fn query(&self) -> Option<&str> {
+    self.query.as_deref()
+  }
+
   pub fn json(&self) -> Option<Vec<GraphQLErrorMessage>> {
     self.json.clone()
   }
+
+  /// GraphQL errors returned by the server, empty for other errors
+  pub fn errors(&self) -> &[GraphQLErrorMessage] {
+    self.json.as_deref().unwrap_or_default()
+  }
+
+  /// GraphQL errors with their extensions deserialized into `E`
+  pub fn errors_as<E>(&self) -> Result<Vec<GraphQLErrorMessage<E>>, GraphQLError>
+  where
+    E: for<'de> Deserialize<'de>,
+  {
+    self
+      .errors()
+      .iter()
+      .map(|err| err.extensions_as())
+      .collect()
+  }
 }
 
 fn format(err: &GraphQLError, f: &mut Formatter<'_>) -> fmt::Result {
@@ -81,13 +274,66 @@ fn format(err: &GraphQLError, f: &mut Formatter<'_>) -> fmt::Result {
 
   let errors = err.json.as_ref();
 
-  for err in errors.unwrap() {
-    writeln!(f, "Message: {}", err.message)?;
+  for error in errors.unwrap() {
+    writeln!(f, "Message: {}", error.message)?;
+    for location in error.locations() {
+      format_location(f, err.query.as_deref(), location)?;
+    }
+    if !error.path().is_empty() {
+      writeln!(f, "Path: {}", format_path(error.path()))?;
+    }
   }
 
   Ok(())
 }
 
+/// Print the offending line of the query with a caret under the column
+fn format_location(
+  f: &mut Formatter<'_>,
+  query: Option<&str>,
+  location: &GraphQLErrorLocation,
+) -> fmt::Result {
+  writeln!(f, "  --> {}:{}", location.line, location.column)?;
+
+  let line = location
+    .line
+    .checked_sub(1)
+    .and_then(|index| query?.lines().nth(index as usize));
+  let line = match line {
+    Some(line) => line,
+    None => return Ok(()),
+  };
+
+  let number = location.line.to_string();
+  let gutter = " ".repeat(number.len());
+  // keep tabs so the caret lines up with the text above it
+  let indent: String = line
+    .chars()
+    .take(location.column.saturating_sub(1) as usize)
+    .map(|c| if c == '\t' { '\t' } else { ' ' })
+    .collect();
+  writeln!(f, " {} |", gutter)?;
+  writeln!(f, " {} | {}", number, line)?;
+  writeln!(f, " {} | {}^", gutter, indent)
+}
+
+/// Join a path as `a.b[2].c`
+fn format_path(path: &[GraphQLErrorPathParam]) -> String {
+  let mut joined = String::new();
+  for param in path {
+    match param {
+      GraphQLErrorPathParam::String(key) => {
+        if !joined.is_empty() {
+          joined.push('.');
+        }
+        joined.push_str(key);
+      }
+      GraphQLErrorPathParam::Number(index) => joined.push_str(&format!("[{}]", index)),
+    }
+  }
+  joined
+}
+
 impl fmt::Display for GraphQLError {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
     format(self, f)
@@ -101,11 +347,29 @@ impl fmt::Debug for GraphQLError {
   }
 }
 
+impl StdError for GraphQLError {
+  fn source(&self) -> Option<&(dyn StdError + 'static)> {
+    self
+      .source
+      .as_deref()
+      .map(|source| source as &(dyn StdError + 'static))
+  }
+}
+
 impl From<Error> for GraphQLError {
   fn from(error: Error) -> Self {
     Self {
+      kind: GraphQLErrorKind::from(&error),
       message: error.to_string(),
       json: None,
+      query: None,
+      source: Some(Arc::new(error)),
     }
   }
 }
+
+impl From<serde_json::Error> for GraphQLError {
+  fn from(error: serde_json::Error) -> Self {
+    Self::with_kind(GraphQLErrorKind::Deserialize, error.to_string()).with_source(error)
+  }
+}
@@ -0,0 +1,309 @@
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::error::{GraphQLError, GraphQLErrorKind, GraphQLErrorMessage, GraphQLErrorPathParam};
+use crate::transport::{ByteStream, TransportStreamResponse};
+use crate::types::SubscriptionStream;
+
+/// Stream of payloads returned by `query_incremental`
+pub type IncrementalStream = SubscriptionStream<IncrementalPayload>;
+
+/// One part of an incremental response, the initial result or a subsequent payload
+// https://github.com/graphql/graphql-spec/blob/main/rfcs/DeferStream.md
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalPayload {
+  /// data of the initial result
+  pub data: Option<Value>,
+  pub errors: Option<Vec<GraphQLErrorMessage>>,
+  /// deferred fragments and streamed items delivered by a subsequent payload
+  pub incremental: Option<Vec<IncrementalPatch>>,
+  /// whether more payloads follow
+  #[serde(default)]
+  pub has_next: bool,
+}
+
+/// Result of a `@defer` fragment or items of a `@stream` field
+#[derive(Deserialize, Debug, Clone)]
+pub struct IncrementalPatch {
+  /// fields of a deferred fragment, merged into the object at `path`
+  pub data: Option<Value>,
+  /// streamed list items, `path` ends with the index of the first item
+  pub items: Option<Vec<Value>>,
+  pub path: Vec<GraphQLErrorPathParam>,
+  pub label: Option<String>,
+  pub errors: Option<Vec<GraphQLErrorMessage>>,
+}
+
+/// Merges incremental payloads into the complete response
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalResult {
+  data: Value,
+  errors: Vec<GraphQLErrorMessage>,
+  has_next: bool,
+}
+
+impl IncrementalResult {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Apply the initial result or a subsequent payload
+  pub fn apply(&mut self, payload: IncrementalPayload) {
+    if let Some(data) = payload.data {
+      merge(&mut self.data, data);
+    }
+    self.errors.extend(payload.errors.unwrap_or_default());
+    for patch in payload.incremental.unwrap_or_default() {
+      self.apply_patch(patch);
+    }
+    self.has_next = payload.has_next;
+  }
+
+  pub fn apply_patch(&mut self, patch: IncrementalPatch) {
+    self.errors.extend(patch.errors.unwrap_or_default());
+
+    if let Some(data) = patch.data {
+      if let Some(target) = get_mut(&mut self.data, &patch.path) {
+        merge(target, data);
+      }
+    }
+
+    if let Some(items) = patch.items {
+      let (index, list_path) = match patch.path.split_last() {
+        Some((GraphQLErrorPathParam::Number(index), list_path)) => (*index as usize, list_path),
+        _ => return,
+      };
+      if let Some(Value::Array(list)) = get_mut(&mut self.data, list_path) {
+        for (offset, item) in items.into_iter().enumerate() {
+          match list.get_mut(index + offset) {
+            Some(existing) => *existing = item,
+            None => list.push(item),
+          }
+        }
+      }
+    }
+  }
+
+  /// Data merged so far
+  pub fn data(&self) -> &Value {
+    &self.data
+  }
+
+  /// Errors received so far
+  pub fn errors(&self) -> &[GraphQLErrorMessage] {
+    &self.errors
+  }
+
+  /// Whether the server announced more payloads
+  pub fn has_next(&self) -> bool {
+    self.has_next
+  }
+
+  /// Deserialize the merged data, GraphQL errors are returned as an error
+  pub fn into_data<K>(self) -> Result<K, GraphQLError>
+  where
+    K: for<'de> Deserialize<'de>,
+  {
+    if !self.errors.is_empty() {
+      return Err(GraphQLError::with_json(self.errors));
+    }
+    serde_json::from_value(self.data).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Deserialize,
+        format!("Failed to parse data: {:?}", e),
+      )
+      .with_source(e)
+    })
+  }
+}
+
+fn get_mut<'a>(value: &'a mut Value, path: &[GraphQLErrorPathParam]) -> Option<&'a mut Value> {
+  path.iter().try_fold(value, |value, param| match param {
+    GraphQLErrorPathParam::String(key) => value.get_mut(key),
+    GraphQLErrorPathParam::Number(index) => value.get_mut(*index as usize),
+  })
+}
+
+/// Deep merge `source` into `target`, objects are merged and everything else is replaced
+fn merge(target: &mut Value, source: Value) {
+  match (target, source) {
+    (Value::Object(target), Value::Object(source)) => {
+      for (key, value) in source {
+        merge(target.entry(key).or_insert(Value::Null), value);
+      }
+    }
+    (target, source) => *target = source,
+  }
+}
+
+/// Splits a `multipart/mixed` body into its parts
+struct PartReader {
+  body: ByteStream,
+  buffer: Vec<u8>,
+  delimiter: Vec<u8>,
+  started: bool,
+  finished: bool,
+}
+
+impl PartReader {
+  fn new(body: ByteStream, boundary: &str) -> Self {
+    Self {
+      body,
+      // a delimiter always starts a line, the body is prefixed with a newline so the first one matches too
+      buffer: b"\n".to_vec(),
+      delimiter: format!("\n--{}", boundary).into_bytes(),
+      started: false,
+      finished: false,
+    }
+  }
+
+  async fn next(&mut self) -> Result<Option<Vec<u8>>, GraphQLError> {
+    loop {
+      if self.finished {
+        return Ok(None);
+      }
+
+      let delimiter = &self.delimiter;
+      let position = self
+        .buffer
+        .windows(delimiter.len())
+        .position(|w| w == delimiter.as_slice());
+      if let Some(position) = position {
+        // the close delimiter is followed by `--`, wait until it can be told apart
+        if self.buffer.len() < position + self.delimiter.len() + 2 && self.read().await? {
+          continue;
+        }
+        let part: Vec<u8> = self.buffer.drain(..position).collect();
+        self.buffer.drain(..self.delimiter.len());
+        if self.buffer.starts_with(b"--") {
+          self.finished = true;
+        }
+
+        let started = self.started;
+        self.started = true;
+        if started {
+          return Ok(Some(part));
+        }
+        continue;
+      }
+
+      if !self.read().await? {
+        return Ok(None);
+      }
+    }
+  }
+
+  /// Read the next chunk, false at the end of the body
+  async fn read(&mut self) -> Result<bool, GraphQLError> {
+    match self.body.next().await {
+      // parts carry json, which never contains a raw carriage return
+      Some(chunk) => {
+        self
+          .buffer
+          .extend(chunk?.into_iter().filter(|byte| *byte != b'\r'));
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+}
+
+/// Body of a part, without the rest of the delimiter line and the part headers
+fn part_body(part: &[u8]) -> &[u8] {
+  let part = part.strip_prefix(b"\n").unwrap_or(part);
+  if let Some(body) = part.strip_prefix(b"\n") {
+    return body;
+  }
+  match part.windows(2).position(|w| w == b"\n\n") {
+    Some(end) => &part[end + 2..],
+    None => &[],
+  }
+}
+
+fn parse_payload(body: &[u8]) -> Result<Option<IncrementalPayload>, GraphQLError> {
+  let value: Value = serde_json::from_slice(body).map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Deserialize,
+      format!(
+        "Failed to parse payload: {:?}. The payload is: {}",
+        e,
+        String::from_utf8_lossy(body)
+      ),
+    )
+    .with_source(e)
+  })?;
+  // empty objects are sent as heartbeats
+  if value == Value::Object(Map::new()) {
+    return Ok(None);
+  }
+  serde_json::from_value(value).map(Some).map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Deserialize,
+      format!("Failed to parse payload: {:?}", e),
+    )
+    .with_source(e)
+  })
+}
+
+/// Whether the media type is `multipart/mixed`, media types are case insensitive
+fn is_multipart_mixed(content_type: &str) -> bool {
+  let media_type = content_type.split(';').next().unwrap_or_default();
+  media_type.trim().eq_ignore_ascii_case("multipart/mixed")
+}
+
+fn boundary(content_type: &str) -> Option<String> {
+  content_type.split(';').find_map(|param| {
+    let (name, value) = param.trim().split_once('=')?;
+    if name.trim().eq_ignore_ascii_case("boundary") {
+      Some(value.trim().trim_matches('"').to_string())
+    } else {
+      None
+    }
+  })
+}
+
+pub(crate) async fn stream(
+  response: TransportStreamResponse,
+) -> Result<IncrementalStream, GraphQLError> {
+  let content_type = response.header("content-type").unwrap_or_default();
+  if !is_multipart_mixed(content_type) {
+    // the server does not support incremental delivery and sent a single result
+    let text = response.text().await?;
+    let payload = parse_payload(text.as_bytes())?.unwrap_or_default();
+    let stream = stream::once(async move { Ok(payload) });
+    #[cfg(not(target_arch = "wasm32"))]
+    return Ok(stream.boxed());
+    #[cfg(target_arch = "wasm32")]
+    return Ok(stream.boxed_local());
+  }
+
+  let boundary = boundary(content_type).unwrap_or_else(|| "-".to_string());
+  let reader = PartReader::new(response.body, &boundary);
+
+  let stream = stream::unfold(Some(reader), |reader| async move {
+    let mut reader = reader?;
+    loop {
+      let part = match reader.next().await {
+        Ok(Some(part)) => part,
+        Ok(None) => return None,
+        Err(e) => return Some((Err(e), None)),
+      };
+      let body = part_body(&part);
+      if body.iter().all(u8::is_ascii_whitespace) {
+        continue;
+      }
+      match parse_payload(body) {
+        Ok(Some(payload)) => return Some((Ok(payload), Some(reader))),
+        Ok(None) => continue,
+        Err(e) => return Some((Err(e), None)),
+      }
+    }
+  });
+
+  #[cfg(not(target_arch = "wasm32"))]
+  return Ok(stream.boxed());
+  #[cfg(target_arch = "wasm32")]
+  return Ok(stream.boxed_local());
+}
@@ -0,0 +1,365 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+use crate::transport::Transport;
+
+/// Standard introspection query, as sent by [`introspect`](GQLClient::introspect)
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      ...FullType
+    }
+    directives {
+      name
+      description
+      locations
+      args {
+        ...InputValue
+      }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args {
+      ...InputValue
+    }
+    type {
+      ...TypeRef
+    }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields {
+    ...InputValue
+  }
+  interfaces {
+    ...TypeRef
+  }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes {
+    ...TypeRef
+  }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type {
+    ...TypeRef
+  }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Fields to add to the [standard introspection query](INTROSPECTION_QUERY),
+/// left out by default since servers without support for them reject the query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IntrospectionOptions {
+  /// ask whether directives are repeatable
+  pub directive_is_repeatable: bool,
+}
+
+impl IntrospectionOptions {
+  pub fn with_directive_is_repeatable(mut self, directive_is_repeatable: bool) -> Self {
+    self.directive_is_repeatable = directive_is_repeatable;
+    self
+  }
+
+  /// Introspection query with the fields of the options
+  pub fn query(&self) -> String {
+    let mut query = INTROSPECTION_QUERY.to_string();
+    if self.directive_is_repeatable {
+      query = query.replacen(
+        "      description\n      locations",
+        "      description\n      isRepeatable\n      locations",
+        1,
+      );
+    }
+    query
+  }
+}
+
+#[derive(Deserialize)]
+struct Introspection {
+  #[serde(rename = "__schema")]
+  schema: Schema,
+}
+
+/// Schema of a GraphQL server, as returned by introspection
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+  #[serde(with = "type_name")]
+  pub query_type: Option<String>,
+  #[serde(with = "type_name")]
+  pub mutation_type: Option<String>,
+  #[serde(with = "type_name")]
+  pub subscription_type: Option<String>,
+  /// named types, including the built-in scalars and the introspection types
+  pub types: Vec<SchemaType>,
+  pub directives: Vec<Directive>,
+}
+
+impl Schema {
+  /// Type with this name
+  pub fn get_type(&self, name: &str) -> Option<&SchemaType> {
+    self.types.iter().find(|ty| ty.name == name)
+  }
+
+  /// Directive with this name, without the `@`
+  pub fn get_directive(&self, name: &str) -> Option<&Directive> {
+    self
+      .directives
+      .iter()
+      .find(|directive| directive.name == name)
+  }
+}
+
+/// Kind of a type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TypeKind {
+  Scalar,
+  Object,
+  Interface,
+  Union,
+  Enum,
+  InputObject,
+  List,
+  NonNull,
+}
+
+/// Named type of the schema. Fields which do not apply to the kind of the type are empty.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaType {
+  pub kind: TypeKind,
+  pub name: String,
+  pub description: Option<String>,
+  /// fields of objects and interfaces
+  #[serde(default, deserialize_with = "nullable")]
+  pub fields: Vec<Field>,
+  /// fields of input objects
+  #[serde(default, deserialize_with = "nullable")]
+  pub input_fields: Vec<InputValue>,
+  /// interfaces implemented by objects and interfaces
+  #[serde(default, deserialize_with = "nullable")]
+  pub interfaces: Vec<TypeRef>,
+  #[serde(default, deserialize_with = "nullable")]
+  pub enum_values: Vec<EnumValue>,
+  /// members of unions, implementations of interfaces
+  #[serde(default, deserialize_with = "nullable")]
+  pub possible_types: Vec<TypeRef>,
+}
+
+impl SchemaType {
+  /// Field of an object or interface with this name
+  pub fn get_field(&self, name: &str) -> Option<&Field> {
+    self.fields.iter().find(|field| field.name == name)
+  }
+
+  /// Whether this is one of the types used by introspection, like `__Type`
+  pub fn is_introspection(&self) -> bool {
+    self.name.starts_with("__")
+  }
+
+  /// Whether this is one of the scalars defined by the specification
+  pub fn is_builtin_scalar(&self) -> bool {
+    self.kind == TypeKind::Scalar
+      && matches!(
+        self.name.as_str(),
+        "Int" | "Float" | "String" | "Boolean" | "ID"
+      )
+  }
+}
+
+/// Field of an object or interface
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+  pub name: String,
+  pub description: Option<String>,
+  pub args: Vec<InputValue>,
+  #[serde(rename = "type")]
+  pub ty: TypeRef,
+  #[serde(default)]
+  pub is_deprecated: bool,
+  pub deprecation_reason: Option<String>,
+}
+
+/// Argument, or field of an input object
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputValue {
+  pub name: String,
+  pub description: Option<String>,
+  #[serde(rename = "type")]
+  pub ty: TypeRef,
+  /// default value, as a GraphQL literal
+  pub default_value: Option<String>,
+}
+
+/// Value of an enum
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumValue {
+  pub name: String,
+  pub description: Option<String>,
+  #[serde(default)]
+  pub is_deprecated: bool,
+  pub deprecation_reason: Option<String>,
+}
+
+/// Directive supported by the server
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directive {
+  pub name: String,
+  pub description: Option<String>,
+  /// whether the directive can be used more than once at the same location,
+  /// only known when asked with [`IntrospectionOptions::directive_is_repeatable`], false otherwise
+  #[serde(default)]
+  pub is_repeatable: bool,
+  /// locations where the directive can be used, like `FIELD` or `OBJECT`
+  pub locations: Vec<String>,
+  pub args: Vec<InputValue>,
+}
+
+/// Reference to a type, with its list and non null wrappers
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeRef {
+  pub kind: TypeKind,
+  /// name of a named type, not set for lists and non null types
+  pub name: Option<String>,
+  /// wrapped type of lists and non null types
+  pub of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+  /// Name of the named type, without the wrappers
+  pub fn named_type(&self) -> &str {
+    match (&self.name, &self.of_type) {
+      (Some(name), _) => name,
+      (None, Some(of_type)) => of_type.named_type(),
+      (None, None) => "",
+    }
+  }
+
+  pub fn is_non_null(&self) -> bool {
+    self.kind == TypeKind::NonNull
+  }
+}
+
+/// Type as written in a query, like `[String!]!`
+impl fmt::Display for TypeRef {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (self.kind, &self.of_type) {
+      (TypeKind::List, Some(of_type)) => write!(f, "[{}]", of_type),
+      (TypeKind::NonNull, Some(of_type)) => write!(f, "{}!", of_type),
+      _ => f.write_str(self.named_type()),
+    }
+  }
+}
+
+/// Null lists are returned for the fields which do not apply to a type kind
+fn nullable<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Deserialize<'de>,
+{
+  Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Root operation types are returned as `{ "name": "Query" }`
+mod type_name {
+  use super::*;
+
+  #[derive(Deserialize, Serialize)]
+  struct Named {
+    name: String,
+  }
+
+  pub(super) fn serialize<S: Serializer>(
+    name: &Option<String>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    name
+      .as_ref()
+      .map(|name| Named { name: name.clone() })
+      .serialize(serializer)
+  }
+
+  pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Option<String>, D::Error> {
+    Ok(Option::<Named>::deserialize(deserializer)?.map(|named| named.name))
+  }
+}
+
+impl<C: Transport + 'static> GQLClient<C> {
+  /// Run the [standard introspection query](INTROSPECTION_QUERY) and return the schema of the server
+  pub async fn introspect(&self) -> Result<Schema, GraphQLError> {
+    self.introspect_with(IntrospectionOptions::default()).await
+  }
+
+  /// Like [`introspect`](Self::introspect), asking for the fields of the options too
+  pub async fn introspect_with(
+    &self,
+    options: IntrospectionOptions,
+  ) -> Result<Schema, GraphQLError> {
+    let introspection = self.query_unwrap::<Introspection>(&options.query()).await?;
+    Ok(introspection.schema)
+  }
+}
@@ -0,0 +1,116 @@
+/// Token of a GraphQL document, borrowing its text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+  Name(&'a str),
+  /// one of `! $ & ( ) ... : = @ [ ] { } |`
+  Punctuator(&'a str),
+  Number(&'a str),
+  /// string with its quotes, escapes are not decoded
+  String(&'a str),
+  /// content of a block string, without its quotes
+  BlockString(&'a str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LexError {
+  UnterminatedString,
+  Unexpected(char),
+}
+
+/// Tokens of a document with their position, without whitespace, commas and comments.
+/// Lexing resumes after an error: past an unexpected character, or at the end of the line of an
+/// unterminated string. An unterminated block string takes the rest of the document.
+pub(crate) fn tokens(
+  document: &str,
+) -> impl Iterator<Item = Result<(usize, Token<'_>), (usize, LexError)>> {
+  let mut position = 0;
+  std::iter::from_fn(move || loop {
+    let rest = &document[position..];
+    let start = position;
+    let c = rest.chars().next()?;
+    let (len, token) = match c {
+      c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {
+        position += c.len_utf8();
+        continue;
+      }
+      '#' => {
+        position += rest.find('\n').unwrap_or(rest.len());
+        continue;
+      }
+      '"' if rest.starts_with("\"\"\"") => match block_string_end(&rest[3..]) {
+        Some(end) => (end + 6, Token::BlockString(&rest[3..end + 3])),
+        None => {
+          return fail(
+            &mut position,
+            rest.len(),
+            start,
+            LexError::UnterminatedString,
+          )
+        }
+      },
+      '"' => match string_end(rest) {
+        Some(end) => (end, Token::String(&rest[..end])),
+        None => {
+          let line = rest.find('\n').unwrap_or(rest.len());
+          return fail(&mut position, line, start, LexError::UnterminatedString);
+        }
+      },
+      c if c == '_' || c.is_ascii_alphabetic() => {
+        let end = rest
+          .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+          .unwrap_or(rest.len());
+        (end, Token::Name(&rest[..end]))
+      }
+      c if c == '-' || c.is_ascii_digit() => {
+        let end = rest[1..]
+          .find(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '+' | '-'))
+          .map_or(rest.len(), |end| end + 1);
+        (end, Token::Number(&rest[..end]))
+      }
+      '.' if rest.starts_with("...") => (3, Token::Punctuator(&rest[..3])),
+      c if "!$&()=:@[]{}|".contains(c) => (1, Token::Punctuator(&rest[..1])),
+      c => return fail(&mut position, c.len_utf8(), start, LexError::Unexpected(c)),
+    };
+    position += len;
+    return Some(Ok((start, token)));
+  })
+}
+
+/// Error at a position, the `skipped` bytes after it are not lexed
+fn fail<T>(
+  position: &mut usize,
+  skipped: usize,
+  start: usize,
+  error: LexError,
+) -> Option<Result<T, (usize, LexError)>> {
+  *position += skipped;
+  Some(Err((start, error)))
+}
+
+/// Position of the closing quotes of a block string, escaped quotes are skipped
+fn block_string_end(rest: &str) -> Option<usize> {
+  let mut from = 0;
+  loop {
+    let end = from + rest[from..].find("\"\"\"")?;
+    if !rest[..end].ends_with('\\') {
+      return Some(end);
+    }
+    from = end + 3;
+  }
+}
+
+/// Position after the closing quote of a string
+fn string_end(rest: &str) -> Option<usize> {
+  let mut chars = rest.char_indices().skip(1);
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\\' => {
+        chars.next();
+      }
+      '"' => return Some(i + 1),
+      '\n' => return None,
+      _ => {}
+    }
+  }
+  None
+}
@@ -79,6 +79,7 @@
 //! or GraphQL query errors in JSON response.
 //! Debug, Display implementation of GraphQLError struct properly displays those error messages.
 //! Additionally, you can also look at JSON content for more detailed output by calling err.json()
+//! GraphQL errors discard the data of the response, use query_with_vars_response to get partial data along with the errors.
 //!
 //! ```rust
 //!use gql_client::Client;
@@ -124,11 +125,49 @@
 //!}
 //! ```
 
+#[cfg(feature = "batching")]
+mod batch;
 mod client;
+mod diff;
+mod document;
 mod error;
+mod incremental;
+mod introspection;
+mod lexer;
+mod middleware;
+mod retry;
+mod runtime;
+mod sdl;
+mod sse;
+#[cfg(feature = "tower")]
+mod tower;
+mod transport;
 mod types;
+mod upload;
+#[cfg(all(feature = "ws", not(target_arch = "wasm32")))]
+mod ws;
 
+pub use async_trait::async_trait;
 pub use client::GQLClient as Client;
+pub use client::GraphQLRequest;
+pub use client::GraphQLResponse;
+pub use diff::{ChangeKind, Criticality, SchemaChange, SchemaDiff};
 pub use error::GraphQLError;
+pub use error::GraphQLErrorKind;
+pub use error::GraphQLErrorLocation;
 pub use error::GraphQLErrorMessage;
+pub use error::GraphQLErrorPathParam;
+pub use incremental::{IncrementalPatch, IncrementalPayload, IncrementalResult, IncrementalStream};
+pub use introspection::{
+  Directive, EnumValue, Field, InputValue, IntrospectionOptions, Schema, SchemaType, TypeKind,
+  TypeRef, INTROSPECTION_QUERY,
+};
+pub use middleware::{Middleware, Next, NextStreaming};
+#[cfg(feature = "tower")]
+pub use tower::TowerTransport;
+pub use transport::{
+  ByteStream, HttpMethod, ReqwestTransport, Transport, TransportRequest, TransportResponse,
+  TransportStreamResponse,
+};
 pub use types::*;
+pub use upload::Upload;
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::error::GraphQLError;
+use crate::transport::{Transport, TransportRequest, TransportResponse, TransportStreamResponse};
+
+/// Code run around every request sent by the client.
+///
+/// Middleware added with [`with_middleware`](crate::Client::with_middleware) are
+/// nested in the order they were added, the first one sees the request first and
+/// the response last. Every attempt goes through the middleware, including retries.
+/// An error returned by a middleware is returned by the query.
+///
+/// ```
+/// use gql_client::{async_trait, GraphQLError, Middleware, Next, TransportRequest, TransportResponse};
+///
+/// struct Timing;
+///
+/// #[async_trait]
+/// impl Middleware for Timing {
+///   async fn handle(
+///     &self,
+///     request: TransportRequest,
+///     next: Next<'_>,
+///   ) -> Result<TransportResponse, GraphQLError> {
+///     let started = std::time::Instant::now();
+///     let response = next.run(request).await;
+///     println!("{:?}", started.elapsed());
+///     response
+///   }
+/// }
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Middleware: Send + Sync {
+  /// Handle a request, `next` sends it through the remaining middleware and the transport.
+  /// The url, headers and serialized body can be changed before, the response after.
+  async fn handle(
+    &self,
+    request: TransportRequest,
+    next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError>;
+
+  /// Handle a request whose response is streamed, for subscriptions over Server-Sent Events
+  /// and incremental delivery. Passes the request on unchanged by default.
+  async fn handle_streaming(
+    &self,
+    request: TransportRequest,
+    next: NextStreaming<'_>,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    next.run(request).await
+  }
+}
+
+/// The rest of the middleware chain, ending with the transport
+pub struct Next<'a> {
+  transport: &'a dyn Transport,
+  middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+  pub(crate) fn new(transport: &'a dyn Transport, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+    Self {
+      transport,
+      middleware,
+    }
+  }
+
+  /// Send the request through the remaining middleware and the transport
+  pub async fn run(self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    match self.middleware.split_first() {
+      Some((middleware, rest)) => {
+        middleware
+          .handle(request, Next::new(self.transport, rest))
+          .await
+      }
+      None => self.transport.send(request).await,
+    }
+  }
+}
+
+/// The rest of the middleware chain for streamed responses, ending with the transport
+pub struct NextStreaming<'a> {
+  transport: &'a dyn Transport,
+  middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> NextStreaming<'a> {
+  pub(crate) fn new(transport: &'a dyn Transport, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+    Self {
+      transport,
+      middleware,
+    }
+  }
+
+  /// Send the request through the remaining middleware and the transport
+  pub async fn run(
+    self,
+    request: TransportRequest,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    match self.middleware.split_first() {
+      Some((middleware, rest)) => {
+        middleware
+          .handle_streaming(request, NextStreaming::new(self.transport, rest))
+          .await
+      }
+      None => self.transport.send_streaming(request).await,
+    }
+  }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::error::GraphQLError;
+use crate::runtime;
+use crate::transport::TransportResponse;
+use crate::types::RetryPolicy;
+
+impl RetryPolicy {
+  /// Delay before the next attempt, `None` if the result must not be retried
+  pub(crate) fn delay(
+    &self,
+    attempt: u32,
+    result: &Result<TransportResponse, GraphQLError>,
+  ) -> Option<Duration> {
+    if attempt >= self.max_attempts {
+      return None;
+    }
+
+    let retry_after = match result {
+      Ok(response) if self.retry_statuses.contains(&response.status) => {
+        response.header("retry-after").and_then(retry_after)
+      }
+      Err(e) if self.retry_kinds.contains(&e.kind()) => None,
+      _ => return None,
+    };
+
+    let max_delay = Duration::from_millis(self.max_delay);
+    if let Some(retry_after) = retry_after {
+      return Some(retry_after.min(max_delay));
+    }
+
+    let backoff = Duration::from_millis(self.base_delay)
+      .checked_mul(2u32.saturating_pow(attempt - 1))
+      .unwrap_or(max_delay)
+      .min(max_delay);
+    if !self.jitter {
+      return Some(backoff);
+    }
+    let half = backoff / 2;
+    Some(half + half.mul_f64(random()))
+  }
+}
+
+/// Parse `Retry-After`, either a number of seconds or an HTTP date
+fn retry_after(value: &str) -> Option<Duration> {
+  if let Ok(seconds) = value.trim().parse::<u64>() {
+    return Some(Duration::from_secs(seconds));
+  }
+  // the system clock is not available on wasm32-unknown-unknown
+  #[cfg(not(target_arch = "wasm32"))]
+  {
+    let date = httpdate::parse_http_date(value.trim()).ok()?;
+    Some(
+      date
+        .duration_since(std::time::SystemTime::now())
+        .unwrap_or_default(),
+    )
+  }
+  #[cfg(target_arch = "wasm32")]
+  None
+}
+
+/// Random number in `[0, 1)`
+fn random() -> f64 {
+  let bits = runtime::random_u64() >> 11;
+  bits as f64 / (1u64 << 53) as f64
+}
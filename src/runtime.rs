@@ -0,0 +1,54 @@
+use std::collections::hash_map::RandomState;
+#[cfg(any(feature = "batching", all(feature = "ws", not(target_arch = "wasm32"))))]
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Random number, from the random keys of the std hasher
+pub(crate) fn random_u64() -> u64 {
+  RandomState::new().build_hasher().finish()
+}
+
+/// Sleep that does not depend on the async runtime of the caller
+pub(crate) async fn sleep(duration: Duration) {
+  #[cfg(not(target_arch = "wasm32"))]
+  futures_timer::Delay::new(duration).await;
+  #[cfg(target_arch = "wasm32")]
+  gloo_timers::future::sleep(duration).await;
+}
+
+/// Wait for a future for at most `duration`, `None` if it did not complete in time
+#[cfg(all(feature = "ws", not(target_arch = "wasm32")))]
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+  let sleep = sleep(duration);
+  futures_util::pin_mut!(future, sleep);
+  match futures_util::future::select(future, sleep).await {
+    futures_util::future::Either::Left((output, _)) => Some(output),
+    futures_util::future::Either::Right(_) => None,
+  }
+}
+
+/// Run a future on a task of its own, so it completes even if the caller is dropped.
+/// The future is given back when there is no runtime to spawn it on.
+#[cfg(all(feature = "batching", not(target_arch = "wasm32")))]
+pub(crate) fn spawn<F>(future: F) -> Result<(), F>
+where
+  F: Future<Output = ()> + Send + 'static,
+{
+  match tokio::runtime::Handle::try_current() {
+    Ok(runtime) => {
+      runtime.spawn(future);
+      Ok(())
+    }
+    Err(_) => Err(future),
+  }
+}
+
+#[cfg(all(feature = "batching", target_arch = "wasm32"))]
+pub(crate) fn spawn<F>(future: F) -> Result<(), F>
+where
+  F: Future<Output = ()> + 'static,
+{
+  wasm_bindgen_futures::spawn_local(future);
+  Ok(())
+}
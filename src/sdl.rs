@@ -0,0 +1,791 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use crate::error::{GraphQLError, GraphQLErrorKind};
+use crate::introspection::{
+  Directive, EnumValue, Field, InputValue, Schema, SchemaType, TypeKind, TypeRef,
+};
+use crate::lexer::{self, LexError};
+
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+pub(crate) const BUILTIN_DIRECTIVES: [&str; 5] =
+  ["skip", "include", "deprecated", "specifiedBy", "oneOf"];
+
+impl Schema {
+  /// Print the schema as GraphQL SDL, in the order of the introspection result.
+  /// Built-in scalars and directives and the introspection types are left out.
+  pub fn to_sdl(&self) -> String {
+    let mut definitions = Vec::new();
+    if let Some(schema) = self.schema_definition() {
+      definitions.push(schema);
+    }
+    definitions.extend(
+      self
+        .directives
+        .iter()
+        .filter(|directive| !BUILTIN_DIRECTIVES.contains(&directive.name.as_str()))
+        .map(print_directive),
+    );
+    definitions.extend(
+      self
+        .types
+        .iter()
+        .filter(|ty| !ty.is_introspection() && !ty.is_builtin_scalar())
+        .map(print_type),
+    );
+
+    let mut sdl = definitions.join("\n\n");
+    sdl.push('\n');
+    sdl
+  }
+
+  /// Write the schema as GraphQL SDL to a file, see [`to_sdl`](Self::to_sdl)
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn write_sdl(&self, path: impl AsRef<Path>) -> Result<(), GraphQLError> {
+    let path = path.as_ref();
+    std::fs::write(path, self.to_sdl()).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Other,
+        format!("Can not write schema to {}: {:?}", path.display(), e),
+      )
+      .with_source(e)
+    })
+  }
+
+  /// `schema` block, only needed when the root types do not have the default names
+  fn schema_definition(&self) -> Option<String> {
+    let roots = [
+      ("query", &self.query_type, "Query"),
+      ("mutation", &self.mutation_type, "Mutation"),
+      ("subscription", &self.subscription_type, "Subscription"),
+    ];
+    // without a schema block, types with the default names are the roots
+    let conventional = roots.iter().all(|(_, name, default)| match name {
+      Some(name) => name == default,
+      None => self.get_type(default).is_none(),
+    });
+    if conventional {
+      return None;
+    }
+
+    let mut schema = String::from("schema {\n");
+    for (operation, name, _) in roots {
+      if let Some(name) = name {
+        let _ = writeln!(schema, "  {}: {}", operation, name);
+      }
+    }
+    schema.push('}');
+    Some(schema)
+  }
+}
+
+fn print_type(ty: &SchemaType) -> String {
+  let mut sdl = print_description(ty.description.as_deref(), "");
+  match ty.kind {
+    TypeKind::Scalar => {
+      let _ = write!(sdl, "scalar {}", ty.name);
+    }
+    TypeKind::Object | TypeKind::Interface => {
+      let keyword = if ty.kind == TypeKind::Object {
+        "type"
+      } else {
+        "interface"
+      };
+      let _ = write!(sdl, "{} {}", keyword, ty.name);
+      if !ty.interfaces.is_empty() {
+        let interfaces: Vec<_> = ty.interfaces.iter().map(TypeRef::named_type).collect();
+        let _ = write!(sdl, " implements {}", interfaces.join(" & "));
+      }
+      sdl.push_str(&print_block(ty.fields.iter().map(print_field)));
+    }
+    TypeKind::Union => {
+      let _ = write!(sdl, "union {}", ty.name);
+      if !ty.possible_types.is_empty() {
+        let members: Vec<_> = ty.possible_types.iter().map(TypeRef::named_type).collect();
+        let _ = write!(sdl, " = {}", members.join(" | "));
+      }
+    }
+    TypeKind::Enum => {
+      let _ = write!(sdl, "enum {}", ty.name);
+      sdl.push_str(&print_block(ty.enum_values.iter().map(print_enum_value)));
+    }
+    TypeKind::InputObject => {
+      let _ = write!(sdl, "input {}", ty.name);
+      sdl.push_str(&print_block(
+        ty.input_fields
+          .iter()
+          .map(|field| print_input_value(field, "  ")),
+      ));
+    }
+    TypeKind::List | TypeKind::NonNull => {}
+  }
+  sdl
+}
+
+/// Fields between braces, nothing for a type without fields
+fn print_block(items: impl Iterator<Item = String>) -> String {
+  let items: Vec<_> = items.collect();
+  if items.is_empty() {
+    return String::new();
+  }
+  format!(" {{\n{}\n}}", items.join("\n"))
+}
+
+fn print_field(field: &Field) -> String {
+  let mut sdl = print_description(field.description.as_deref(), "  ");
+  let _ = write!(
+    sdl,
+    "  {}{}: {}",
+    field.name,
+    print_args(&field.args, "  "),
+    field.ty
+  );
+  sdl.push_str(&print_deprecated(
+    field.is_deprecated,
+    field.deprecation_reason.as_deref(),
+  ));
+  sdl
+}
+
+fn print_enum_value(value: &EnumValue) -> String {
+  let mut sdl = print_description(value.description.as_deref(), "  ");
+  let _ = write!(sdl, "  {}", value.name);
+  sdl.push_str(&print_deprecated(
+    value.is_deprecated,
+    value.deprecation_reason.as_deref(),
+  ));
+  sdl
+}
+
+fn print_directive(directive: &Directive) -> String {
+  let mut sdl = print_description(directive.description.as_deref(), "");
+  let _ = write!(
+    sdl,
+    "directive @{}{}{} on {}",
+    directive.name,
+    print_args(&directive.args, ""),
+    if directive.is_repeatable {
+      " repeatable"
+    } else {
+      ""
+    },
+    directive.locations.join(" | ")
+  );
+  sdl
+}
+
+/// Arguments on one line, or one per line when one of them has a description
+fn print_args(args: &[InputValue], indentation: &str) -> String {
+  if args.is_empty() {
+    return String::new();
+  }
+  if args.iter().all(|arg| arg.description.is_none()) {
+    let args: Vec<_> = args.iter().map(|arg| print_input_value(arg, "")).collect();
+    return format!("({})", args.join(", "));
+  }
+
+  let nested = format!("{}  ", indentation);
+  let args: Vec<_> = args
+    .iter()
+    .map(|arg| print_input_value(arg, &nested))
+    .collect();
+  format!("(\n{}\n{})", args.join("\n"), indentation)
+}
+
+fn print_input_value(value: &InputValue, indentation: &str) -> String {
+  let mut sdl = print_description(value.description.as_deref(), indentation);
+  let _ = write!(sdl, "{}{}: {}", indentation, value.name, value.ty);
+  if let Some(default_value) = &value.default_value {
+    let _ = write!(sdl, " = {}", default_value);
+  }
+  sdl
+}
+
+fn print_deprecated(is_deprecated: bool, reason: Option<&str>) -> String {
+  match reason {
+    _ if !is_deprecated => String::new(),
+    None | Some(DEFAULT_DEPRECATION_REASON) => " @deprecated".to_string(),
+    Some(reason) => format!(" @deprecated(reason: {})", print_string(reason)),
+  }
+}
+
+/// Description as a block string, followed by a new line
+fn print_description(description: Option<&str>, indentation: &str) -> String {
+  let description = match description {
+    Some(description) if !description.is_empty() => description.replace("\"\"\"", "\\\"\"\""),
+    _ => return String::new(),
+  };
+  // a quote at the end would merge with the closing quotes
+  if !description.contains('\n') && !description.ends_with('"') {
+    return format!("{}\"\"\"{}\"\"\"\n", indentation, description);
+  }
+
+  let mut sdl = format!("{}\"\"\"\n", indentation);
+  for line in description.lines() {
+    if line.is_empty() {
+      sdl.push('\n');
+    } else {
+      let _ = writeln!(sdl, "{}{}", indentation, line);
+    }
+  }
+  let _ = writeln!(sdl, "{}\"\"\"", indentation);
+  sdl
+}
+
+/// GraphQL string literal, its escapes are the same as json ones
+fn print_string(value: &str) -> String {
+  serde_json::Value::String(value.to_string()).to_string()
+}
+
+impl Schema {
+  /// Parse a schema from GraphQL SDL, like the one printed by [`to_sdl`](Self::to_sdl).
+  /// Type extensions are merged into their type. Built-in scalars and directives are not added.
+  pub fn from_sdl(sdl: &str) -> Result<Schema, GraphQLError> {
+    let mut parser = Parser {
+      sdl,
+      tokens: lex(sdl)?,
+      index: 0,
+    };
+    parser.document()
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+  Name(String),
+  Punctuator(char),
+  Number(String),
+  String(String),
+}
+
+/// Tokens of a document with their position, strings decoded
+fn lex(sdl: &str) -> Result<Vec<(usize, Token)>, GraphQLError> {
+  lexer::tokens(sdl)
+    .map(|token| {
+      let (position, token) = token.map_err(|(position, error)| {
+        let message = match error {
+          LexError::UnterminatedString => "Unterminated string".to_string(),
+          LexError::Unexpected(c) => format!("Unexpected {:?}", c),
+        };
+        syntax_error(sdl, position, message)
+      })?;
+      let token = match token {
+        lexer::Token::Name(name) => Token::Name(name.to_string()),
+        lexer::Token::Number(number) => Token::Number(number.to_string()),
+        // escapes of GraphQL strings are the same as json ones
+        lexer::Token::String(raw) => Token::String(
+          serde_json::from_str(raw).map_err(|_| syntax_error(sdl, position, "Invalid string"))?,
+        ),
+        lexer::Token::BlockString(raw) => Token::String(block_string_value(raw)),
+        // fragment spreads are not part of type system documents
+        lexer::Token::Punctuator("...") => {
+          return Err(syntax_error(sdl, position, format!("Unexpected {:?}", '.')))
+        }
+        lexer::Token::Punctuator(punctuator) => {
+          Token::Punctuator(punctuator.chars().next().unwrap_or_default())
+        }
+      };
+      Ok((position, token))
+    })
+    .collect()
+}
+
+/// Value of a block string, without the common indentation and the blank first and last lines
+fn block_string_value(raw: &str) -> String {
+  let raw = raw.replace("\\\"\"\"", "\"\"\"");
+  let lines: Vec<_> = raw.lines().collect();
+  let indentation = lines
+    .iter()
+    .skip(1)
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+    .min()
+    .unwrap_or(0);
+  let mut lines: Vec<_> = lines
+    .iter()
+    .enumerate()
+    .map(|(i, line)| match i {
+      0 => line,
+      _ => line.get(indentation..).unwrap_or(""),
+    })
+    .collect();
+  while lines.first().is_some_and(|line| line.trim().is_empty()) {
+    lines.remove(0);
+  }
+  while lines.last().is_some_and(|line| line.trim().is_empty()) {
+    lines.pop();
+  }
+  lines.join("\n")
+}
+
+fn syntax_error(sdl: &str, position: usize, message: impl AsRef<str>) -> GraphQLError {
+  let before = &sdl[..position];
+  let line = before.matches('\n').count() + 1;
+  let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+    .chars()
+    .count()
+    + 1;
+  GraphQLError::with_kind(
+    GraphQLErrorKind::Other,
+    format!("Invalid SDL at {}:{}: {}", line, column, message.as_ref()),
+  )
+}
+
+struct Parser<'a> {
+  sdl: &'a str,
+  tokens: Vec<(usize, Token)>,
+  index: usize,
+}
+
+impl Parser<'_> {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.index).map(|(_, token)| token)
+  }
+
+  fn error(&self, message: impl AsRef<str>) -> GraphQLError {
+    let position = self
+      .tokens
+      .get(self.index)
+      .map_or(self.sdl.len(), |(position, _)| *position);
+    syntax_error(self.sdl, position, message)
+  }
+
+  fn next(&mut self) -> Result<Token, GraphQLError> {
+    let token = self
+      .peek()
+      .cloned()
+      .ok_or_else(|| self.error("Unexpected end of document"))?;
+    self.index += 1;
+    Ok(token)
+  }
+
+  fn eat(&mut self, punctuator: char) -> bool {
+    let found = self.peek() == Some(&Token::Punctuator(punctuator));
+    if found {
+      self.index += 1;
+    }
+    found
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    let found = matches!(self.peek(), Some(Token::Name(name)) if name == keyword);
+    if found {
+      self.index += 1;
+    }
+    found
+  }
+
+  fn expect(&mut self, punctuator: char) -> Result<(), GraphQLError> {
+    if self.eat(punctuator) {
+      return Ok(());
+    }
+    Err(self.error(format!("Expected {:?}", punctuator)))
+  }
+
+  fn name(&mut self) -> Result<String, GraphQLError> {
+    match self.peek() {
+      Some(Token::Name(name)) => {
+        let name = name.clone();
+        self.index += 1;
+        Ok(name)
+      }
+      _ => Err(self.error("Expected a name")),
+    }
+  }
+
+  fn description(&mut self) -> Option<String> {
+    match self.peek() {
+      Some(Token::String(description)) => {
+        let description = description.clone();
+        self.index += 1;
+        Some(description)
+      }
+      _ => None,
+    }
+  }
+
+  fn document(&mut self) -> Result<Schema, GraphQLError> {
+    let mut schema = Schema {
+      query_type: None,
+      mutation_type: None,
+      subscription_type: None,
+      types: Vec::new(),
+      directives: Vec::new(),
+    };
+    let mut has_schema_definition = false;
+    let mut extensions = Vec::new();
+
+    while self.peek().is_some() {
+      let description = self.description();
+      let extend = self.eat_keyword("extend");
+      let start = self.index;
+      match self.name()?.as_str() {
+        "schema" => {
+          self.directives()?;
+          self.expect('{')?;
+          while !self.eat('}') {
+            let operation = self.name()?;
+            self.expect(':')?;
+            let name = Some(self.name()?);
+            match operation.as_str() {
+              "query" => schema.query_type = name,
+              "mutation" => schema.mutation_type = name,
+              "subscription" => schema.subscription_type = name,
+              _ => return Err(self.error(format!("Unknown operation type {}", operation))),
+            }
+          }
+          has_schema_definition = true;
+        }
+        "directive" if !extend => {
+          schema.directives.push(self.directive(description)?);
+        }
+        keyword => {
+          let kind = match keyword {
+            "scalar" => TypeKind::Scalar,
+            "type" => TypeKind::Object,
+            "interface" => TypeKind::Interface,
+            "union" => TypeKind::Union,
+            "enum" => TypeKind::Enum,
+            "input" => TypeKind::InputObject,
+            _ => {
+              self.index = start;
+              return Err(self.error(format!("Unexpected {}", keyword)));
+            }
+          };
+          let ty = self.type_definition(kind, description)?;
+          if extend {
+            extensions.push((start, ty));
+          } else {
+            schema.types.push(ty);
+          }
+        }
+      }
+    }
+
+    for (start, extension) in extensions {
+      let ty = schema
+        .types
+        .iter_mut()
+        .find(|ty| ty.name == extension.name && ty.kind == extension.kind);
+      let ty = match ty {
+        Some(ty) => ty,
+        None => {
+          self.index = start;
+          return Err(self.error(format!("Can not extend unknown type {}", extension.name)));
+        }
+      };
+      ty.fields.extend(extension.fields);
+      ty.input_fields.extend(extension.input_fields);
+      ty.interfaces.extend(extension.interfaces);
+      ty.enum_values.extend(extension.enum_values);
+      ty.possible_types.extend(extension.possible_types);
+    }
+
+    if !has_schema_definition {
+      let types = &schema.types;
+      let root = |name: &str| {
+        types
+          .iter()
+          .any(|ty| ty.name == name)
+          .then(|| name.to_string())
+      };
+      let (query, mutation, subscription) = (root("Query"), root("Mutation"), root("Subscription"));
+      schema.query_type = query;
+      schema.mutation_type = mutation;
+      schema.subscription_type = subscription;
+    }
+    resolve(&mut schema)?;
+    Ok(schema)
+  }
+
+  fn directive(&mut self, description: Option<String>) -> Result<Directive, GraphQLError> {
+    self.expect('@')?;
+    let name = self.name()?;
+    let args = self.arguments_definition()?;
+    let is_repeatable = self.eat_keyword("repeatable");
+    if !self.eat_keyword("on") {
+      return Err(self.error("Expected on"));
+    }
+    self.eat('|');
+    let mut locations = vec![self.name()?];
+    while self.eat('|') {
+      locations.push(self.name()?);
+    }
+    Ok(Directive {
+      name,
+      description,
+      is_repeatable,
+      locations,
+      args,
+    })
+  }
+
+  fn type_definition(
+    &mut self,
+    kind: TypeKind,
+    description: Option<String>,
+  ) -> Result<SchemaType, GraphQLError> {
+    let mut ty = SchemaType {
+      kind,
+      name: self.name()?,
+      description,
+      fields: Vec::new(),
+      input_fields: Vec::new(),
+      interfaces: Vec::new(),
+      enum_values: Vec::new(),
+      possible_types: Vec::new(),
+    };
+    match kind {
+      TypeKind::Object | TypeKind::Interface => {
+        if self.eat_keyword("implements") {
+          self.eat('&');
+          ty.interfaces.push(named(self.name()?));
+          while self.eat('&') {
+            ty.interfaces.push(named(self.name()?));
+          }
+        }
+        self.directives()?;
+        if self.eat('{') {
+          while !self.eat('}') {
+            ty.fields.push(self.field()?);
+          }
+        }
+      }
+      TypeKind::Union => {
+        self.directives()?;
+        if self.eat('=') {
+          self.eat('|');
+          ty.possible_types.push(named(self.name()?));
+          while self.eat('|') {
+            ty.possible_types.push(named(self.name()?));
+          }
+        }
+      }
+      TypeKind::Enum => {
+        self.directives()?;
+        if self.eat('{') {
+          while !self.eat('}') {
+            let description = self.description();
+            let name = self.name()?;
+            let deprecation = self.directives()?;
+            ty.enum_values.push(EnumValue {
+              name,
+              description,
+              is_deprecated: deprecation.is_some(),
+              deprecation_reason: deprecation,
+            });
+          }
+        }
+      }
+      TypeKind::InputObject => {
+        self.directives()?;
+        if self.eat('{') {
+          while !self.eat('}') {
+            ty.input_fields.push(self.input_value()?);
+          }
+        }
+      }
+      TypeKind::Scalar | TypeKind::List | TypeKind::NonNull => {
+        self.directives()?;
+      }
+    }
+    Ok(ty)
+  }
+
+  fn field(&mut self) -> Result<Field, GraphQLError> {
+    let description = self.description();
+    let name = self.name()?;
+    let args = self.arguments_definition()?;
+    self.expect(':')?;
+    let ty = self.type_ref()?;
+    let deprecation = self.directives()?;
+    Ok(Field {
+      name,
+      description,
+      args,
+      ty,
+      is_deprecated: deprecation.is_some(),
+      deprecation_reason: deprecation,
+    })
+  }
+
+  fn arguments_definition(&mut self) -> Result<Vec<InputValue>, GraphQLError> {
+    let mut args = Vec::new();
+    if self.eat('(') {
+      while !self.eat(')') {
+        args.push(self.input_value()?);
+      }
+    }
+    Ok(args)
+  }
+
+  fn input_value(&mut self) -> Result<InputValue, GraphQLError> {
+    let description = self.description();
+    let name = self.name()?;
+    self.expect(':')?;
+    let ty = self.type_ref()?;
+    let default_value = if self.eat('=') {
+      Some(self.value()?)
+    } else {
+      None
+    };
+    self.directives()?;
+    Ok(InputValue {
+      name,
+      description,
+      ty,
+      default_value,
+    })
+  }
+
+  /// Type reference, named types are resolved once the whole document is parsed
+  fn type_ref(&mut self) -> Result<TypeRef, GraphQLError> {
+    let ty = if self.eat('[') {
+      let of_type = self.type_ref()?;
+      self.expect(']')?;
+      wrap(TypeKind::List, of_type)
+    } else {
+      named(self.name()?)
+    };
+    if self.eat('!') {
+      return Ok(wrap(TypeKind::NonNull, ty));
+    }
+    Ok(ty)
+  }
+
+  /// Value literal, printed the way servers print default values
+  fn value(&mut self) -> Result<String, GraphQLError> {
+    match self.next()? {
+      Token::Punctuator('$') => Ok(format!("${}", self.name()?)),
+      Token::Punctuator('[') => {
+        let mut items = Vec::new();
+        while !self.eat(']') {
+          items.push(self.value()?);
+        }
+        Ok(format!("[{}]", items.join(", ")))
+      }
+      Token::Punctuator('{') => {
+        let mut fields = Vec::new();
+        while !self.eat('}') {
+          let name = self.name()?;
+          self.expect(':')?;
+          fields.push(format!("{}: {}", name, self.value()?));
+        }
+        Ok(format!("{{{}}}", fields.join(", ")))
+      }
+      Token::Name(name) | Token::Number(name) => Ok(name),
+      Token::String(value) => Ok(print_string(&value)),
+      Token::Punctuator(_) => {
+        self.index -= 1;
+        Err(self.error("Expected a value"))
+      }
+    }
+  }
+
+  /// Skip directives, returns the deprecation reason when `@deprecated` is one of them
+  fn directives(&mut self) -> Result<Option<String>, GraphQLError> {
+    let mut deprecation = None;
+    while self.eat('@') {
+      let name = self.name()?;
+      let mut reason = None;
+      if self.eat('(') {
+        while !self.eat(')') {
+          let argument = self.name()?;
+          self.expect(':')?;
+          let value = self.value()?;
+          if argument == "reason" {
+            reason = serde_json::from_str(&value).ok();
+          }
+        }
+      }
+      if name == "deprecated" {
+        deprecation = Some(reason.unwrap_or_else(|| DEFAULT_DEPRECATION_REASON.to_string()));
+      }
+    }
+    Ok(deprecation)
+  }
+}
+
+fn named(name: String) -> TypeRef {
+  TypeRef {
+    kind: TypeKind::Scalar,
+    name: Some(name),
+    of_type: None,
+  }
+}
+
+fn wrap(kind: TypeKind, of_type: TypeRef) -> TypeRef {
+  TypeRef {
+    kind,
+    name: None,
+    of_type: Some(Box::new(of_type)),
+  }
+}
+
+/// Set the kind of named type references, and the implementations of interfaces
+fn resolve(schema: &mut Schema) -> Result<(), GraphQLError> {
+  let mut kinds: HashMap<String, TypeKind> = ["Int", "Float", "String", "Boolean", "ID"]
+    .iter()
+    .map(|name| (name.to_string(), TypeKind::Scalar))
+    .collect();
+  kinds.extend(schema.types.iter().map(|ty| (ty.name.clone(), ty.kind)));
+
+  let resolve_ref = |ty: &mut TypeRef| resolve_type_ref(ty, &kinds);
+
+  let mut implementations = Vec::new();
+  for ty in &mut schema.types {
+    for field in &mut ty.fields {
+      resolve_ref(&mut field.ty)?;
+      for arg in &mut field.args {
+        resolve_ref(&mut arg.ty)?;
+      }
+    }
+    for field in &mut ty.input_fields {
+      resolve_ref(&mut field.ty)?;
+    }
+    for ty in ty.interfaces.iter_mut().chain(&mut ty.possible_types) {
+      resolve_ref(ty)?;
+    }
+    if ty.kind == TypeKind::Object {
+      for interface in &ty.interfaces {
+        implementations.push((interface.named_type().to_string(), named(ty.name.clone())));
+      }
+    }
+  }
+  for directive in &mut schema.directives {
+    for arg in &mut directive.args {
+      resolve_ref(&mut arg.ty)?;
+    }
+  }
+
+  for (interface, mut implementation) in implementations {
+    resolve_ref(&mut implementation)?;
+    let interface = schema
+      .types
+      .iter_mut()
+      .find(|ty| ty.name == interface && ty.kind == TypeKind::Interface);
+    if let Some(interface) = interface {
+      interface.possible_types.push(implementation);
+    }
+  }
+  Ok(())
+}
+
+fn resolve_type_ref(
+  ty: &mut TypeRef,
+  kinds: &HashMap<String, TypeKind>,
+) -> Result<(), GraphQLError> {
+  if let Some(of_type) = ty.of_type.as_deref_mut() {
+    return resolve_type_ref(of_type, kinds);
+  }
+  let name = ty.named_type();
+  ty.kind = *kinds.get(name).ok_or_else(|| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Other,
+      format!("Invalid SDL: unknown type {}", name),
+    )
+  })?;
+  Ok(())
+}
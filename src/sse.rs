@@ -0,0 +1,128 @@
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+
+use crate::client::GraphQLResponse;
+use crate::error::{GraphQLError, GraphQLErrorKind, GraphQLErrorPayload};
+use crate::transport::ByteStream;
+use crate::types::SubscriptionStream;
+
+// https://github.com/enisdenjo/graphql-sse/blob/master/PROTOCOL.md
+struct Event {
+  event: String,
+  data: String,
+}
+
+/// Splits a `text/event-stream` body into events
+struct EventReader {
+  body: ByteStream,
+  buffer: Vec<u8>,
+}
+
+impl EventReader {
+  async fn next(&mut self) -> Result<Option<Event>, GraphQLError> {
+    loop {
+      if let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+        let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+        let block = String::from_utf8(block).map_err(|e| {
+          GraphQLError::with_kind(
+            GraphQLErrorKind::Deserialize,
+            format!("Failed to parse event: {:?}", e),
+          )
+          .with_source(e)
+        })?;
+        if let Some(event) = parse_event(&block) {
+          return Ok(Some(event));
+        }
+        continue;
+      }
+
+      match self.body.next().await {
+        // events are separated by blank lines, drop carriage returns to only deal with \n
+        Some(chunk) => self
+          .buffer
+          .extend(chunk?.into_iter().filter(|byte| *byte != b'\r')),
+        None => return Ok(None),
+      }
+    }
+  }
+}
+
+fn parse_event(block: &str) -> Option<Event> {
+  let mut event = String::new();
+  let mut data: Vec<&str> = Vec::new();
+  for line in block.lines() {
+    // comments, used by servers as keep-alive
+    if line.is_empty() || line.starts_with(':') {
+      continue;
+    }
+    let (field, value) = match line.find(':') {
+      Some(i) => (&line[..i], &line[i + 1..]),
+      None => (line, ""),
+    };
+    let value = value.strip_prefix(' ').unwrap_or(value);
+    match field {
+      "event" => event = value.to_string(),
+      "data" => data.push(value),
+      _ => {}
+    }
+  }
+
+  if event.is_empty() && data.is_empty() {
+    return None;
+  }
+  Some(Event {
+    event,
+    data: data.join("\n"),
+  })
+}
+
+pub(crate) fn subscription<K>(body: ByteStream) -> SubscriptionStream<K>
+where
+  K: for<'de> Deserialize<'de> + Send + 'static,
+{
+  let reader = EventReader {
+    body,
+    buffer: Vec::new(),
+  };
+
+  let stream = stream::unfold(Some(reader), |reader| async move {
+    let mut reader = reader?;
+    loop {
+      let event = match reader.next().await {
+        Ok(Some(event)) => event,
+        Ok(None) => return None,
+        Err(e) => return Some((Err(e), None)),
+      };
+
+      match event.event.as_str() {
+        // events without a name are dispatched as `message`
+        "next" | "message" | "" if !event.data.is_empty() => {
+          let result = serde_json::from_str(&event.data)
+            .map_err(|e| {
+              GraphQLError::with_kind(
+                GraphQLErrorKind::Deserialize,
+                format!("Failed to parse event: {:?}", e),
+              )
+              .with_source(e)
+            })
+            .and_then(GraphQLResponse::from_value);
+          return Some((result, Some(reader)));
+        }
+        "complete" => return None,
+        "error" => {
+          let error = match serde_json::from_str::<GraphQLErrorPayload>(&event.data) {
+            Ok(payload) => payload.into(),
+            Err(_) => GraphQLError::with_kind(GraphQLErrorKind::GraphQL, event.data),
+          };
+          return Some((Err(error), None));
+        }
+        _ => continue,
+      }
+    }
+  });
+
+  #[cfg(not(target_arch = "wasm32"))]
+  return stream.boxed();
+  #[cfg(target_arch = "wasm32")]
+  return stream.boxed_local();
+}
@@ -0,0 +1,154 @@
+use std::error::Error as StdError;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[cfg(not(target_arch = "wasm32"))]
+use ::tower::timeout::error::Elapsed;
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures_util::future::LocalBoxFuture;
+use futures_util::future::{self, FutureExt};
+use futures_util::lock::Mutex;
+use serde_json::Value;
+use tower_service::Service;
+
+use crate::client::{GQLClient, GraphQLRequest, GraphQLResponse};
+use crate::error::{GraphQLError, GraphQLErrorKind};
+use crate::transport::{ReqwestTransport, Transport, TransportRequest, TransportResponse};
+use crate::types::{ClientConfig, RequestOptions};
+
+#[cfg(not(target_arch = "wasm32"))]
+type ServiceFuture<T> = BoxFuture<'static, Result<T, GraphQLError>>;
+#[cfg(target_arch = "wasm32")]
+type ServiceFuture<T> = LocalBoxFuture<'static, Result<T, GraphQLError>>;
+
+/// Runs queries like [`query_with_options`](GQLClient::query_with_options),
+/// so tower layers can wrap the client.
+impl<C> Service<GraphQLRequest> for GQLClient<C>
+where
+  C: Transport + 'static,
+{
+  type Response = GraphQLResponse<Value>;
+  type Error = GraphQLError;
+  type Future = ServiceFuture<Self::Response>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, request: GraphQLRequest) -> Self::Future {
+    let client = self.clone();
+    let response = async move {
+      let options = RequestOptions {
+        operation_name: request.operation_name,
+        ..Default::default()
+      };
+      client
+        .query_with_options(&request.query, request.variables, options)
+        .await
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    return response.boxed();
+    #[cfg(target_arch = "wasm32")]
+    return response.boxed_local();
+  }
+}
+
+impl Service<TransportRequest> for ReqwestTransport {
+  type Response = TransportResponse;
+  type Error = GraphQLError;
+  type Future = ServiceFuture<Self::Response>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, request: TransportRequest) -> Self::Future {
+    let transport = self.clone();
+    let response = async move { transport.send(request).await };
+    #[cfg(not(target_arch = "wasm32"))]
+    return response.boxed();
+    #[cfg(target_arch = "wasm32")]
+    return response.boxed_local();
+  }
+}
+
+/// Transport sending requests through a tower service, usually layers around a [`ReqwestTransport`].
+///
+/// A single service is shared by all requests and clones of the transport, so the state of
+/// layers like concurrency and rate limits is shared too. Requests wait their turn to poll the
+/// service for readiness and to be called, their responses are then awaited concurrently.
+///
+/// Errors of the layers are returned with the [`Transport`](GraphQLErrorKind::Transport) kind,
+/// unless they already are a [`GraphQLError`] or come from a timeout layer,
+/// which gets the [`Timeout`](GraphQLErrorKind::Timeout) kind.
+#[derive(Debug)]
+pub struct TowerTransport<S> {
+  service: Arc<Mutex<S>>,
+}
+
+impl<S> Clone for TowerTransport<S> {
+  fn clone(&self) -> Self {
+    Self {
+      service: self.service.clone(),
+    }
+  }
+}
+
+impl<S> TowerTransport<S> {
+  pub fn new(service: S) -> Self {
+    Self {
+      service: Arc::new(Mutex::new(service)),
+    }
+  }
+
+  /// The service, `None` while clones of the transport share it
+  pub fn into_inner(self) -> Option<S> {
+    Arc::try_unwrap(self.service).ok().map(Mutex::into_inner)
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S> Transport for TowerTransport<S>
+where
+  S: Service<TransportRequest, Response = TransportResponse> + Send,
+  S::Error: Into<Box<dyn StdError + Send + Sync>>,
+  S::Future: Send,
+{
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    let response = {
+      let mut service = self.service.lock().await;
+      future::poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .map_err(service_error)?;
+      service.call(request)
+    };
+    response.await.map_err(service_error)
+  }
+}
+
+fn service_error(error: impl Into<Box<dyn StdError + Send + Sync>>) -> GraphQLError {
+  let error = match error.into().downcast::<GraphQLError>() {
+    Ok(error) => return *error,
+    Err(error) => error,
+  };
+  #[cfg(not(target_arch = "wasm32"))]
+  if error.is::<Elapsed>() {
+    return GraphQLError::with_kind(GraphQLErrorKind::Timeout, error.to_string())
+      .with_boxed_source(error);
+  }
+  GraphQLError::with_kind(GraphQLErrorKind::Transport, error.to_string()).with_boxed_source(error)
+}
+
+impl<S> GQLClient<TowerTransport<S>>
+where
+  TowerTransport<S>: Transport,
+{
+  /// Create a client sending its requests through a tower service
+  pub fn new_with_service(config: ClientConfig, service: S) -> Self {
+    Self::new_with_transport(config, TowerTransport::new(service))
+  }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::convert::TryInto;
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::stream::BoxStream;
+#[cfg(target_arch = "wasm32")]
+use futures_util::stream::LocalBoxStream;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GraphQLError, GraphQLErrorKind};
+use crate::types::{ClientConfig, GQLProxy};
+
+/// Http method of a [`TransportRequest`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HttpMethod {
+  /// the request is encoded in the url and the body is empty
+  Get,
+  #[default]
+  Post,
+}
+
+/// A serialized GraphQL request, ready to be sent over the wire
+#[derive(Clone, Debug)]
+pub struct TransportRequest {
+  pub method: HttpMethod,
+  /// the url the request is sent to
+  pub url: String,
+  /// request headers, names are lowercased
+  pub headers: HashMap<String, String>,
+  /// serialized request body
+  pub body: Vec<u8>,
+}
+
+/// Raw response returned by a [`Transport`]
+#[derive(Clone, Debug)]
+pub struct TransportResponse {
+  /// http status code
+  pub status: u16,
+  /// response headers, names are lowercased
+  pub headers: HashMap<String, String>,
+  /// response body text
+  pub body: String,
+}
+
+impl TransportResponse {
+  pub fn new(status: u16, headers: HashMap<String, String>, body: impl Into<String>) -> Self {
+    Self {
+      status,
+      headers: headers
+        .into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value))
+        .collect(),
+      body: body.into(),
+    }
+  }
+
+  /// Get a header value by name, case insensitive
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.get(&name.to_lowercase()).map(|v| v.as_str())
+  }
+
+  pub fn is_success(&self) -> bool {
+    (200..300).contains(&self.status)
+  }
+}
+
+/// Chunks of a streamed response body
+#[cfg(not(target_arch = "wasm32"))]
+pub type ByteStream = BoxStream<'static, Result<Vec<u8>, GraphQLError>>;
+#[cfg(target_arch = "wasm32")]
+pub type ByteStream = LocalBoxStream<'static, Result<Vec<u8>, GraphQLError>>;
+
+/// Response returned by [`Transport::send_streaming`], the body is read as it arrives
+pub struct TransportStreamResponse {
+  /// http status code
+  pub status: u16,
+  /// response headers, names are lowercased
+  pub headers: HashMap<String, String>,
+  /// response body chunks
+  pub body: ByteStream,
+}
+
+impl TransportStreamResponse {
+  /// Get a header value by name, case insensitive
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.get(&name.to_lowercase()).map(|v| v.as_str())
+  }
+
+  pub fn is_success(&self) -> bool {
+    (200..300).contains(&self.status)
+  }
+
+  /// Read the whole body as text
+  pub async fn text(self) -> Result<String, GraphQLError> {
+    let chunks: Vec<Vec<u8>> = self.body.try_collect().await?;
+    String::from_utf8(chunks.concat()).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Deserialize,
+        format!("Can not get response: {:?}", e),
+      )
+      .with_source(e)
+    })
+  }
+}
+
+impl From<TransportResponse> for TransportStreamResponse {
+  fn from(response: TransportResponse) -> Self {
+    let body = response.body.into_bytes();
+    let body = stream::once(async move { Ok(body) });
+    Self {
+      status: response.status,
+      headers: response.headers,
+      #[cfg(not(target_arch = "wasm32"))]
+      body: body.boxed(),
+      #[cfg(target_arch = "wasm32")]
+      body: body.boxed_local(),
+    }
+  }
+}
+
+/// Sends serialized GraphQL requests.
+///
+/// [`ReqwestTransport`] is used by default, implement this trait to plug in
+/// another http client, a mock or an in-process executor.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport: Send + Sync {
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError>;
+
+  /// Send a request and stream the response body, used for subscriptions over Server-Sent Events.
+  /// The default implementation waits for the whole response.
+  async fn send_streaming(
+    &self,
+    request: TransportRequest,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    Ok(self.send(request).await?.into())
+  }
+}
+
+/// Default transport, backed by reqwest.
+///
+/// The underlying `reqwest::Client` is built once, on first use, and shared
+/// between clones so connections and TLS sessions are reused across queries.
+#[derive(Clone, Debug)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct ReqwestTransport {
+  timeout: Option<u64>,
+  proxy: Option<GQLProxy>,
+  client: Arc<OnceLock<Client>>,
+}
+
+impl ReqwestTransport {
+  pub fn new(config: &ClientConfig) -> Self {
+    Self {
+      timeout: config.timeout,
+      proxy: config.proxy.clone(),
+      client: Arc::new(OnceLock::new()),
+    }
+  }
+
+  /// Use a caller supplied client, timeout and proxy from the config are not applied
+  pub fn with_client(client: Client) -> Self {
+    Self {
+      timeout: None,
+      proxy: None,
+      client: Arc::new(OnceLock::from(client)),
+    }
+  }
+
+  fn request(&self, request: TransportRequest) -> Result<RequestBuilder, GraphQLError> {
+    let client = self.client()?;
+    let mut builder = match request.method {
+      HttpMethod::Get => client.get(&request.url),
+      HttpMethod::Post => client.post(&request.url).body(request.body),
+    };
+    for (name, value) in &request.headers {
+      builder = builder.header(name, value);
+    }
+    Ok(builder)
+  }
+
+  fn client(&self) -> Result<&Client, GraphQLError> {
+    if let Some(client) = self.client.get() {
+      return Ok(client);
+    }
+    let client = self.build_client()?;
+    Ok(self.client.get_or_init(|| client))
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn build_client(&self) -> Result<Client, GraphQLError> {
+    Ok(Client::new())
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn build_client(&self) -> Result<Client, GraphQLError> {
+    let mut builder =
+      Client::builder().timeout(std::time::Duration::from_secs(self.timeout.unwrap_or(5)));
+    if let Some(proxy) = &self.proxy {
+      builder = builder.proxy(proxy.clone().try_into()?);
+    }
+    builder.build().map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Config,
+        format!("Can not create client: {:?}", e),
+      )
+      .with_source(e)
+    })
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for ReqwestTransport {
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    let raw_response = self.request(request)?.send().await?;
+    let status = raw_response.status().as_u16();
+    let headers = response_headers(&raw_response);
+    let body = raw_response.text().await.map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::from(&e),
+        format!("Can not get response: {:?}", e),
+      )
+      .with_source(e)
+    })?;
+
+    Ok(TransportResponse {
+      status,
+      headers,
+      body,
+    })
+  }
+
+  async fn send_streaming(
+    &self,
+    request: TransportRequest,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    let builder = self.request(request)?;
+    // streams stay open as long as the server sends events, the client timeout would cut them off
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder.timeout(std::time::Duration::MAX);
+
+    let raw_response = builder.send().await?;
+    let status = raw_response.status().as_u16();
+    let headers = response_headers(&raw_response);
+    let body = raw_response
+      .bytes_stream()
+      .map_ok(|chunk| chunk.to_vec())
+      .map_err(GraphQLError::from);
+
+    Ok(TransportStreamResponse {
+      status,
+      headers,
+      #[cfg(not(target_arch = "wasm32"))]
+      body: body.boxed(),
+      #[cfg(target_arch = "wasm32")]
+      body: body.boxed_local(),
+    })
+  }
+}
+
+fn response_headers(response: &reqwest::Response) -> HashMap<String, String> {
+  response
+    .headers()
+    .iter()
+    .filter_map(|(name, value)| {
+      value
+        .to_str()
+        .ok()
+        .map(|value| (name.as_str().to_string(), value.to_string()))
+    })
+    .collect()
+}
@@ -1,14 +1,26 @@
 use std::collections::HashMap;
-#[cfg(not(target_arch = "wasm32"))]
 use std::convert::TryFrom;
 
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::stream::BoxStream;
+#[cfg(target_arch = "wasm32")]
+use futures_util::stream::LocalBoxStream;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[cfg(not(target_arch = "wasm32"))]
+use crate::error::GraphQLErrorKind;
+use crate::transport::HttpMethod;
 use crate::GraphQLError;
 
+/// Stream of subscription results, ends when the server completes the subscription
+#[cfg(not(target_arch = "wasm32"))]
+pub type SubscriptionStream<K> = BoxStream<'static, Result<K, GraphQLError>>;
+#[cfg(target_arch = "wasm32")]
+pub type SubscriptionStream<K> = LocalBoxStream<'static, Result<K, GraphQLError>>;
+
 /// GQL client config
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct ClientConfig {
   /// the endpoint about graphql server
   pub endpoint: String,
@@ -18,6 +30,280 @@ pub struct ClientConfig {
   pub headers: Option<HashMap<String, String>>,
   /// request proxy
   pub proxy: Option<GQLProxy>,
+  /// websocket subprotocol for subscriptions, negotiated with the server when not set.
+  /// The server must answer with the subprotocol it accepts, the connection fails otherwise
+  pub ws_protocol: Option<WsProtocol>,
+  /// retry failed queries, no retry when not set
+  pub retry: Option<RetryPolicy>,
+  /// automatic persisted queries, the query hash is sent instead of the full query when set
+  pub persisted_queries: Option<PersistedQueries>,
+  /// trusted documents, only operations of the manifest are sent, by id
+  pub persisted_documents: Option<PersistedDocuments>,
+  /// http method of queries, POST when not set, mutations are always sent with POST
+  pub query_method: Option<HttpMethod>,
+  /// send concurrent queries in batches, each query is sent alone when not set
+  #[cfg(feature = "batching")]
+  pub batching: Option<Batching>,
+}
+
+/// automatic batching of concurrent queries, the server must accept an array of operations.
+/// Mutations, and queries sent with GET, with extensions or as persisted queries are not batched.
+/// Batches are sent from a task spawned on the current tokio runtime. Outside of a tokio runtime,
+/// as with async-std, smol or `futures::executor`, the caller starting or filling a batch sends it
+/// itself, and the batch is not sent if that caller is dropped before the end of the window.
+#[cfg(feature = "batching")]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Batching {
+  /// time to wait for other queries after the first one of a batch, unit: milliseconds
+  pub window: u64,
+  /// operations per batch, a full batch is sent without waiting for the end of the window
+  pub max_size: usize,
+}
+
+#[cfg(feature = "batching")]
+impl Default for Batching {
+  fn default() -> Self {
+    Self {
+      window: 5,
+      max_size: 10,
+    }
+  }
+}
+
+/// options of a single request, overriding the client config
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+  /// http method of the query, mutations are always sent with POST
+  pub method: Option<HttpMethod>,
+  /// operation of the document to execute, detected when the document has a single named operation
+  pub operation_name: Option<String>,
+  /// sent as the `extensions` of the request
+  pub extensions: Map<String, Value>,
+}
+
+impl RequestOptions {
+  pub fn with_method(mut self, method: HttpMethod) -> Self {
+    self.method = Some(method);
+    self
+  }
+
+  pub fn with_operation_name(mut self, operation_name: impl AsRef<str>) -> Self {
+    self.operation_name = Some(operation_name.as_ref().to_string());
+    self
+  }
+
+  pub fn with_extension(mut self, name: impl AsRef<str>, value: Value) -> Self {
+    self.extensions.insert(name.as_ref().to_string(), value);
+    self
+  }
+}
+
+/// automatic persisted queries options
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PersistedQueries {
+  /// send hashed queries with GET so CDNs can cache them, mutations are always sent with POST
+  pub use_get: bool,
+}
+
+/// retry policy with exponential backoff
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+  /// attempts including the first one
+  pub max_attempts: u32,
+  /// delay before the first retry, doubled after each attempt, unit: milliseconds
+  pub base_delay: u64,
+  /// upper bound of the delay, including the one asked by `Retry-After`, unit: milliseconds
+  pub max_delay: u64,
+  /// wait a random delay between half and all of the backoff delay
+  pub jitter: bool,
+  /// response status codes to retry
+  pub retry_statuses: Vec<u16>,
+  /// error kinds to retry
+  pub retry_kinds: Vec<GraphQLErrorKind>,
+  /// retry documents containing a mutation, which may not be idempotent
+  pub retry_mutations: bool,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      base_delay: 100,
+      max_delay: 10_000,
+      jitter: true,
+      retry_statuses: vec![429, 502, 503, 504],
+      retry_kinds: vec![GraphQLErrorKind::Transport, GraphQLErrorKind::Timeout],
+      retry_mutations: false,
+    }
+  }
+}
+
+impl ClientConfig {
+  pub fn new(endpoint: impl AsRef<str>) -> Self {
+    Self {
+      endpoint: endpoint.as_ref().to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_timeout(mut self, timeout: u64) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+    self.headers = Some(headers);
+    self
+  }
+
+  pub fn with_header(mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+    self
+      .headers
+      .get_or_insert_with(HashMap::new)
+      .insert(name.as_ref().to_string(), value.as_ref().to_string());
+    self
+  }
+
+  pub fn with_proxy(mut self, proxy: GQLProxy) -> Self {
+    self.proxy = Some(proxy);
+    self
+  }
+
+  pub fn with_ws_protocol(mut self, ws_protocol: WsProtocol) -> Self {
+    self.ws_protocol = Some(ws_protocol);
+    self
+  }
+
+  pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+    self.retry = Some(retry);
+    self
+  }
+
+  pub fn with_persisted_queries(mut self, persisted_queries: PersistedQueries) -> Self {
+    self.persisted_queries = Some(persisted_queries);
+    self
+  }
+
+  pub fn with_persisted_documents(mut self, persisted_documents: PersistedDocuments) -> Self {
+    self.persisted_documents = Some(persisted_documents);
+    self
+  }
+
+  pub fn with_query_method(mut self, query_method: HttpMethod) -> Self {
+    self.query_method = Some(query_method);
+    self
+  }
+
+  #[cfg(feature = "batching")]
+  pub fn with_batching(mut self, batching: Batching) -> Self {
+    self.batching = Some(batching);
+    self
+  }
+}
+
+/// websocket subprotocol used for subscriptions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WsProtocol {
+  /// `graphql-transport-ws`, spoken by the graphql-ws library
+  GraphQLTransportWs,
+  /// legacy `graphql-ws`, spoken by subscriptions-transport-ws
+  GraphQLWs,
+}
+
+/// trusted documents manifest
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "Manifest")]
+pub struct PersistedDocuments {
+  /// operation id to document
+  manifest: HashMap<String, String>,
+  /// field the id is sent in
+  pub id_field: DocumentIdField,
+  /// document without surrounding whitespace to operation id
+  #[serde(skip)]
+  ids: HashMap<String, String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Manifest {
+  manifest: HashMap<String, String>,
+  id_field: DocumentIdField,
+}
+
+impl TryFrom<Manifest> for PersistedDocuments {
+  type Error = GraphQLError;
+
+  fn try_from(manifest: Manifest) -> Result<Self, Self::Error> {
+    Ok(Self::new(manifest.manifest)?.with_id_field(manifest.id_field))
+  }
+}
+
+impl PersistedDocuments {
+  /// Use a manifest of operation id to document, fails if two ids have the same document
+  pub fn new(manifest: HashMap<String, String>) -> Result<Self, GraphQLError> {
+    let mut ids: HashMap<String, String> = HashMap::new();
+    for (id, document) in &manifest {
+      if let Some(other) = ids.insert(document.trim().to_string(), id.clone()) {
+        let (first, second) = if other < *id {
+          (&other, id)
+        } else {
+          (id, &other)
+        };
+        return Err(GraphQLError::with_kind(
+          GraphQLErrorKind::Config,
+          format!(
+            "Operations {} and {} of the manifest have the same document",
+            first, second
+          ),
+        ));
+      }
+    }
+    Ok(Self {
+      manifest,
+      id_field: DocumentIdField::default(),
+      ids,
+    })
+  }
+
+  /// Load a manifest, a json object of operation id to document
+  pub fn from_manifest(json: &str) -> Result<Self, GraphQLError> {
+    let manifest = serde_json::from_str(json).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Config,
+        format!("Failed to parse manifest: {:?}", e),
+      )
+      .with_source(e)
+    })?;
+    Self::new(manifest)
+  }
+
+  pub fn with_id_field(mut self, id_field: DocumentIdField) -> Self {
+    self.id_field = id_field;
+    self
+  }
+
+  /// Operation id to document
+  pub fn manifest(&self) -> &HashMap<String, String> {
+    &self.manifest
+  }
+
+  /// Id of a document, surrounding whitespace is ignored
+  pub fn id_of(&self, document: &str) -> Option<&str> {
+    self.ids.get(document.trim()).map(|id| id.as_str())
+  }
+}
+
+/// request field carrying the id of a trusted document
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DocumentIdField {
+  /// `documentId`, from the GraphQL over HTTP persisted documents proposal
+  #[default]
+  DocumentId,
+  /// `id`, used by Relay and older servers
+  Id,
 }
 
 /// proxy type
@@ -56,7 +342,9 @@ impl TryFrom<GQLProxy> for reqwest::Proxy {
       ProxyType::Https => reqwest::Proxy::https(gql_proxy.schema),
       ProxyType::All => reqwest::Proxy::all(gql_proxy.schema),
     }
-    .map_err(|e| Self::Error::with_text(format!("{:?}", e)))?;
+    .map_err(|e| {
+      Self::Error::with_kind(GraphQLErrorKind::Config, format!("{:?}", e)).with_source(e)
+    })?;
     Ok(proxy)
   }
 }
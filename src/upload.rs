@@ -0,0 +1,312 @@
+use std::cell::RefCell;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+
+use futures_util::io::{AsyncRead, AsyncReadExt};
+use serde::ser::Error as _;
+use serde::{Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+use crate::error::{GraphQLError, GraphQLErrorKind};
+use crate::runtime;
+
+// https://github.com/jaydenseric/graphql-multipart-request-spec
+const PLACEHOLDER: &str = "\u{0}gql_client::Upload:";
+
+thread_local! {
+  /// Uploads met while serializing variables, set only during `collect`
+  static UPLOADS: RefCell<Option<Vec<Upload>>> = const { RefCell::new(None) };
+}
+
+/// Uploads with their path in the operation, like `variables.files.0`
+pub(crate) type Files = Vec<(String, Upload)>;
+
+#[cfg(not(target_arch = "wasm32"))]
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+#[cfg(target_arch = "wasm32")]
+type Reader = Box<dyn AsyncRead + Unpin>;
+
+/// A reader can only be read once, by the first clone of the upload sent
+#[cfg(not(target_arch = "wasm32"))]
+type SharedReader = Arc<Mutex<Option<Reader>>>;
+#[cfg(target_arch = "wasm32")]
+type SharedReader = Rc<RefCell<Option<Reader>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn share(reader: Reader) -> SharedReader {
+  Arc::new(Mutex::new(Some(reader)))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn share(reader: Reader) -> SharedReader {
+  Rc::new(RefCell::new(Some(reader)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn take(reader: &SharedReader) -> Option<Reader> {
+  reader.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn take(reader: &SharedReader) -> Option<Reader> {
+  reader.borrow_mut().take()
+}
+
+#[derive(Clone)]
+enum Source {
+  Bytes(Arc<Vec<u8>>),
+  Reader(SharedReader),
+}
+
+/// File uploaded with the query, to be used in variables as a value of the `Upload` scalar.
+///
+/// Queries with uploads are sent as `multipart/form-data`, following the GraphQL multipart request spec.
+/// Files are read in memory before the request is sent.
+///
+/// Uploads are found while the variables are serialized by the client, so they have to be
+/// part of the variables given to the query, not of a `serde_json::Value` built beforehand.
+/// Serializing an upload anywhere else fails, including in batches and subscriptions.
+#[derive(Clone)]
+pub struct Upload {
+  filename: String,
+  content_type: Option<String>,
+  source: Source,
+}
+
+impl Upload {
+  pub fn from_bytes(bytes: impl Into<Vec<u8>>, filename: impl AsRef<str>) -> Self {
+    Self::new(Source::Bytes(Arc::new(bytes.into())), filename)
+  }
+
+  /// Upload a file, read now so sending it does not depend on the async runtime of the caller
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Self, GraphQLError> {
+    let path = path.as_ref();
+    let filename = path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    let bytes = std::fs::read(path).map_err(|e| read_error(&filename, e))?;
+    Ok(Self::from_bytes(bytes, filename))
+  }
+
+  /// Upload the content of a reader, read when the query is sent. The query can only be sent once.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn from_reader(
+    reader: impl AsyncRead + Send + Unpin + 'static,
+    filename: impl AsRef<str>,
+  ) -> Self {
+    Self::new(Source::Reader(share(Box::new(reader))), filename)
+  }
+
+  /// Upload the content of a reader, read when the query is sent. The query can only be sent once.
+  #[cfg(target_arch = "wasm32")]
+  pub fn from_reader(reader: impl AsyncRead + Unpin + 'static, filename: impl AsRef<str>) -> Self {
+    Self::new(Source::Reader(share(Box::new(reader))), filename)
+  }
+
+  fn new(source: Source, filename: impl AsRef<str>) -> Self {
+    Self {
+      filename: filename.as_ref().to_string(),
+      content_type: None,
+      source,
+    }
+  }
+
+  /// Mime type of the file, `application/octet-stream` when not set
+  pub fn with_content_type(mut self, content_type: impl AsRef<str>) -> Self {
+    self.content_type = Some(content_type.as_ref().to_string());
+    self
+  }
+
+  pub fn filename(&self) -> &str {
+    &self.filename
+  }
+
+  pub fn content_type(&self) -> Option<&str> {
+    self.content_type.as_deref()
+  }
+
+  async fn read(&self) -> Result<Vec<u8>, GraphQLError> {
+    match &self.source {
+      Source::Bytes(bytes) => Ok(bytes.to_vec()),
+      Source::Reader(reader) => {
+        let mut reader = take(reader).ok_or_else(|| {
+          GraphQLError::with_kind(
+            GraphQLErrorKind::Other,
+            format!("Upload {} has already been read", self.filename),
+          )
+        })?;
+        let mut bytes = Vec::new();
+        reader
+          .read_to_end(&mut bytes)
+          .await
+          .map_err(|e| read_error(&self.filename, e))?;
+        Ok(bytes)
+      }
+    }
+  }
+}
+
+fn read_error(filename: &str, e: std::io::Error) -> GraphQLError {
+  GraphQLError::with_kind(
+    GraphQLErrorKind::Other,
+    format!("Can not read upload {}: {:?}", filename, e),
+  )
+  .with_source(e)
+}
+
+impl fmt::Debug for Upload {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Upload")
+      .field("filename", &self.filename)
+      .field("content_type", &self.content_type)
+      .finish()
+  }
+}
+
+/// Serialized as null, the file is sent in its own part.
+/// Fails when the upload is not serialized by the client as the variables of a query.
+impl Serialize for Upload {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let index = UPLOADS.with(|uploads| {
+      let mut uploads = uploads.borrow_mut();
+      let uploads = uploads.as_mut()?;
+      uploads.push(self.clone());
+      Some(uploads.len() - 1)
+    });
+    match index {
+      Some(index) => serializer.serialize_str(&format!("{}{}", PLACEHOLDER, index)),
+      None => Err(S::Error::custom(format!(
+        "Upload {} can only be sent in the variables of a query",
+        self.filename
+      ))),
+    }
+  }
+}
+
+/// Run a serialization, collecting the uploads it meets
+fn collect<R>(serialize: impl FnOnce() -> R) -> (R, Vec<Upload>) {
+  let outer = UPLOADS.with(|uploads| uploads.borrow_mut().replace(Vec::new()));
+  let result = serialize();
+  let uploads = UPLOADS.with(|uploads| std::mem::replace(&mut *uploads.borrow_mut(), outer));
+  (result, uploads.unwrap_or_default())
+}
+
+/// Variables of a query, serialized once
+pub(crate) enum Variables {
+  /// without uploads, sent as they are serialized
+  Json(Box<RawValue>),
+  /// with their uploads replaced with null, and the uploads with their path in the operation
+  Uploads(Value, Files),
+}
+
+/// Serialize variables, collecting the uploads they contain
+pub(crate) fn serialize<T: Serialize>(variables: &T) -> Result<Variables, GraphQLError> {
+  let (raw, uploads) = collect(|| serde_json::value::to_raw_value(variables));
+  let raw = raw.map_err(serialize_error)?;
+  if uploads.is_empty() {
+    return Ok(Variables::Json(raw));
+  }
+
+  let mut value = serde_json::from_str(raw.get()).map_err(serialize_error)?;
+  let mut files = Vec::new();
+  replace_placeholders(&mut value, "variables".to_string(), &uploads, &mut files);
+  Ok(Variables::Uploads(value, files))
+}
+
+fn serialize_error(e: serde_json::Error) -> GraphQLError {
+  GraphQLError::with_kind(
+    GraphQLErrorKind::Serialize,
+    format!("Failed to serialize variables: {:?}", e),
+  )
+  .with_source(e)
+}
+
+fn replace_placeholders(value: &mut Value, path: String, uploads: &[Upload], files: &mut Files) {
+  match value {
+    Value::String(string) => {
+      let upload = string
+        .strip_prefix(PLACEHOLDER)
+        .and_then(|index| index.parse::<usize>().ok())
+        .and_then(|index| uploads.get(index));
+      if let Some(upload) = upload {
+        files.push((path, upload.clone()));
+        *value = Value::Null;
+      }
+    }
+    Value::Array(items) => {
+      for (index, item) in items.iter_mut().enumerate() {
+        replace_placeholders(item, format!("{}.{}", path, index), uploads, files);
+      }
+    }
+    Value::Object(object) => {
+      for (key, item) in object.iter_mut() {
+        replace_placeholders(item, format!("{}.{}", path, key), uploads, files);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Build a `multipart/form-data` body with the `operations`, the `map` and a part per file.
+/// Returns the content type, with the boundary, and the body.
+pub(crate) async fn multipart(
+  operations: &[u8],
+  files: Files,
+) -> Result<(String, Vec<u8>), GraphQLError> {
+  let boundary = format!("gql-client-{:016x}", runtime::random_u64());
+  let mut map = Map::new();
+  for (index, (path, _)) in files.iter().enumerate() {
+    map.insert(
+      index.to_string(),
+      Value::Array(vec![Value::String(path.clone())]),
+    );
+  }
+
+  let mut body = Vec::new();
+  let mut part = |headers: String, content: &[u8]| {
+    body.extend_from_slice(format!("--{}\r\n{}\r\n\r\n", boundary, headers).as_bytes());
+    body.extend_from_slice(content);
+    body.extend_from_slice(b"\r\n");
+  };
+  part(
+    "Content-Disposition: form-data; name=\"operations\"".to_string(),
+    operations,
+  );
+  part(
+    "Content-Disposition: form-data; name=\"map\"".to_string(),
+    Value::Object(map).to_string().as_bytes(),
+  );
+  for (index, (_, upload)) in files.iter().enumerate() {
+    let content = upload.read().await?;
+    let headers = format!(
+      "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}",
+      index,
+      escape(&upload.filename),
+      upload
+        .content_type
+        .as_deref()
+        .unwrap_or("application/octet-stream")
+    );
+    part(headers, &content);
+  }
+  body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+  Ok((format!("multipart/form-data; boundary={}", boundary), body))
+}
+
+/// Escape a filename for a quoted header parameter
+fn escape(filename: &str) -> String {
+  filename
+    .replace('"', "%22")
+    .replace('\r', "%0D")
+    .replace('\n', "%0A")
+}
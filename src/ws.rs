@@ -0,0 +1,341 @@
+use std::time::Duration;
+
+use futures_util::stream;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::client::{GraphQLResponse, RequestBody};
+use crate::error::{GraphQLError, GraphQLErrorKind, GraphQLErrorPayload};
+use crate::runtime;
+use crate::types::SubscriptionStream;
+use crate::{ClientConfig, WsProtocol};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+const SUBSCRIPTION_ID: &str = "1";
+
+// https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md
+// https://github.com/apollographql/subscriptions-transport-ws/blob/master/PROTOCOL.md
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage<'a, T: Serialize> {
+  ConnectionInit,
+  Subscribe {
+    id: &'a str,
+    payload: &'a RequestBody<T>,
+  },
+  Start {
+    id: &'a str,
+    payload: &'a RequestBody<T>,
+  },
+  Complete {
+    id: &'a str,
+  },
+  Stop {
+    id: &'a str,
+  },
+  Pong,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+  ConnectionAck,
+  ConnectionError {
+    payload: Value,
+  },
+  Ping,
+  #[serde(alias = "data")]
+  Next {
+    payload: Value,
+  },
+  Error {
+    payload: GraphQLErrorPayload,
+  },
+  Complete,
+  #[serde(other)]
+  Unknown,
+}
+
+impl WsProtocol {
+  fn subprotocol(&self) -> &'static str {
+    match self {
+      WsProtocol::GraphQLTransportWs => "graphql-transport-ws",
+      WsProtocol::GraphQLWs => "graphql-ws",
+    }
+  }
+
+  fn from_subprotocol(name: &str) -> Option<Self> {
+    [WsProtocol::GraphQLTransportWs, WsProtocol::GraphQLWs]
+      .iter()
+      .copied()
+      .find(|protocol| protocol.subprotocol() == name)
+  }
+}
+
+pub(crate) async fn subscribe<K, T>(
+  config: &ClientConfig,
+  body: RequestBody<T>,
+) -> Result<SubscriptionStream<K>, GraphQLError>
+where
+  K: for<'de> Deserialize<'de> + Send + 'static,
+  T: Serialize,
+{
+  // the same default as the http transport, a server that never acks would hang forever otherwise
+  let timeout = Duration::from_secs(config.timeout.unwrap_or(5));
+  let (mut socket, protocol) = runtime::timeout(timeout, handshake(config))
+    .await
+    .ok_or_else(|| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Timeout,
+        "Timed out waiting for connection_ack",
+      )
+    })??;
+
+  let message = match protocol {
+    WsProtocol::GraphQLTransportWs => ClientMessage::Subscribe {
+      id: SUBSCRIPTION_ID,
+      payload: &body,
+    },
+    WsProtocol::GraphQLWs => ClientMessage::Start {
+      id: SUBSCRIPTION_ID,
+      payload: &body,
+    },
+  };
+  send(&mut socket, &message).await?;
+
+  let subscription = Subscription {
+    socket: Some(socket),
+    protocol,
+  };
+  let stream = stream::unfold(Some(subscription), |subscription| async move {
+    let mut subscription = subscription?;
+    loop {
+      let socket = subscription.socket.as_mut()?;
+      let message = match receive(socket).await {
+        Ok(Some(message)) => message,
+        Ok(None) => return None,
+        Err(e) => return Some((Err(e), subscription.ended())),
+      };
+
+      match message {
+        ServerMessage::Next { payload } => {
+          return Some((GraphQLResponse::from_value(payload), Some(subscription)))
+        }
+        ServerMessage::Error { payload } => {
+          return Some((Err(payload.into()), subscription.ended()))
+        }
+        ServerMessage::Complete => {
+          subscription.ended();
+          return None;
+        }
+        ServerMessage::Ping => {
+          if let Err(e) = send(socket, &ClientMessage::<()>::Pong).await {
+            return Some((Err(e), subscription.ended()));
+          }
+        }
+        _ => continue,
+      }
+    }
+  });
+
+  Ok(stream.boxed())
+}
+
+/// Socket of a running subscription, the subscription is stopped on the server when it is dropped
+struct Subscription {
+  socket: Option<Socket>,
+  protocol: WsProtocol,
+}
+
+impl Subscription {
+  /// The subscription was ended by the server or the connection, there is nothing left to stop
+  fn ended(mut self) -> Option<Self> {
+    self.socket = None;
+    None
+  }
+}
+
+impl Drop for Subscription {
+  fn drop(&mut self) {
+    let mut socket = match self.socket.take() {
+      Some(socket) => socket,
+      None => return,
+    };
+    let message = match self.protocol {
+      WsProtocol::GraphQLTransportWs => ClientMessage::<()>::Complete {
+        id: SUBSCRIPTION_ID,
+      },
+      WsProtocol::GraphQLWs => ClientMessage::<()>::Stop {
+        id: SUBSCRIPTION_ID,
+      },
+    };
+    // the socket can only be written to asynchronously, outside of a runtime it is just closed
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+      runtime.spawn(async move {
+        if send(&mut socket, &message).await.is_ok() {
+          let _ = socket.close(None).await;
+        }
+      });
+    }
+  }
+}
+
+/// Open the websocket and initialize the connection, done once the server acknowledged it
+async fn handshake(config: &ClientConfig) -> Result<(Socket, WsProtocol), GraphQLError> {
+  let (mut socket, protocol) = connect(config).await?;
+
+  send(&mut socket, &ClientMessage::<()>::ConnectionInit).await?;
+  loop {
+    match receive(&mut socket).await? {
+      Some(ServerMessage::ConnectionAck) => return Ok((socket, protocol)),
+      Some(ServerMessage::ConnectionError { payload }) => {
+        return Err(GraphQLError::with_kind(
+          GraphQLErrorKind::Protocol,
+          format!("Connection rejected: {}", payload),
+        ))
+      }
+      Some(ServerMessage::Ping) => send(&mut socket, &ClientMessage::<()>::Pong).await?,
+      Some(_) => continue,
+      None => {
+        return Err(GraphQLError::with_kind(
+          GraphQLErrorKind::Protocol,
+          "Connection closed before connection_ack",
+        ))
+      }
+    }
+  }
+}
+
+/// Open the websocket, offering every supported subprotocol unless one is forced by the config.
+/// The server has to answer with the subprotocol it speaks.
+async fn connect(config: &ClientConfig) -> Result<(Socket, WsProtocol), GraphQLError> {
+  let mut url = Url::parse(&config.endpoint).map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Config,
+      format!("Wrong endpoint: {}. {:?}", config.endpoint, e),
+    )
+    .with_source(e)
+  })?;
+  let scheme = match url.scheme() {
+    "https" | "wss" => "wss",
+    _ => "ws",
+  };
+  url.set_scheme(scheme).map_err(|_| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Config,
+      format!("Wrong endpoint: {}", config.endpoint),
+    )
+  })?;
+
+  let mut request = url.as_str().into_client_request().map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Config,
+      format!("Can not create request: {:?}", e),
+    )
+    .with_source(e)
+  })?;
+  let headers = request.headers_mut();
+  let offered = match config.ws_protocol {
+    Some(protocol) => protocol.subprotocol(),
+    None => "graphql-transport-ws, graphql-ws",
+  };
+  headers.insert("sec-websocket-protocol", HeaderValue::from_static(offered));
+  if let Some(config_headers) = &config.headers {
+    for (name, value) in config_headers {
+      let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+        GraphQLError::with_kind(
+          GraphQLErrorKind::Config,
+          format!("Invalid header name: {}. {:?}", name, e),
+        )
+        .with_source(e)
+      })?;
+      let value = HeaderValue::from_str(value).map_err(|e| {
+        GraphQLError::with_kind(
+          GraphQLErrorKind::Config,
+          format!("Invalid header value: {}. {:?}", value, e),
+        )
+        .with_source(e)
+      })?;
+      headers.insert(name, value);
+    }
+  }
+
+  let (socket, response) = tokio_tungstenite::connect_async(request)
+    .await
+    .map_err(|e| {
+      GraphQLError::with_kind(error_kind(&e), format!("Can not connect: {:?}", e)).with_source(e)
+    })?;
+  // the handshake fails unless the server answers with one of the offered subprotocols
+  let protocol = response
+    .headers()
+    .get("sec-websocket-protocol")
+    .and_then(|value| value.to_str().ok())
+    .and_then(WsProtocol::from_subprotocol)
+    .ok_or_else(|| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Protocol,
+        "The server did not accept a subscription protocol",
+      )
+    })?;
+  Ok((socket, protocol))
+}
+
+async fn send<T: Serialize>(
+  socket: &mut Socket,
+  message: &ClientMessage<'_, T>,
+) -> Result<(), GraphQLError> {
+  let text = serde_json::to_string(message).map_err(|e| {
+    GraphQLError::with_kind(
+      GraphQLErrorKind::Serialize,
+      format!("Failed to serialize message: {:?}", e),
+    )
+    .with_source(e)
+  })?;
+  socket.send(Message::text(text)).await.map_err(|e| {
+    GraphQLError::with_kind(error_kind(&e), format!("Can not send message: {:?}", e)).with_source(e)
+  })
+}
+
+/// Wait for the next protocol message, `None` once the connection is closed
+async fn receive(socket: &mut Socket) -> Result<Option<ServerMessage>, GraphQLError> {
+  while let Some(message) = socket.next().await {
+    let message = message.map_err(|e| {
+      GraphQLError::with_kind(error_kind(&e), format!("Can not get message: {:?}", e))
+        .with_source(e)
+    })?;
+    let text = match message {
+      Message::Text(text) => text,
+      Message::Close(_) => return Ok(None),
+      _ => continue,
+    };
+    let message = serde_json::from_str(&text).map_err(|e| {
+      GraphQLError::with_kind(
+        GraphQLErrorKind::Deserialize,
+        format!(
+          "Failed to parse message: {:?}. The message is: {}",
+          e,
+          text.as_str()
+        ),
+      )
+      .with_source(e)
+    })?;
+    return Ok(Some(message));
+  }
+  Ok(None)
+}
+
+fn error_kind(error: &WsError) -> GraphQLErrorKind {
+  match error {
+    WsError::Http(response) => GraphQLErrorKind::HttpStatus(response.status().as_u16()),
+    WsError::Url(_) | WsError::HttpFormat(_) => GraphQLErrorKind::Config,
+    WsError::Protocol(_) | WsError::Utf8 => GraphQLErrorKind::Protocol,
+    _ => GraphQLErrorKind::Transport,
+  }
+}
@@ -0,0 +1,110 @@
+mod server;
+
+use crate::server::{serve, Response};
+use gql_client::{Client, GraphQLErrorKind, GraphQLRequest};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Post {
+  post: Option<Value>,
+}
+
+#[tokio::test]
+async fn sends_operations_in_one_request() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"[
+        {"data": {"hello": "world"}},
+        {"data": {"post": null}, "errors": [{"message": "Post not found", "path": ["post"]}]}
+      ]"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let results = client
+    .batch(vec![
+      GraphQLRequest::new("{ hello }"),
+      GraphQLRequest::new("query Post($id: ID!) { post(id: $id) { title } }")
+        .with_variables(json!({ "id": 1 }))
+        .unwrap(),
+    ])
+    .await
+    .unwrap();
+
+  let mut results = results.into_iter();
+  let hello = results.next().unwrap().into_typed::<Hello>().unwrap();
+  assert_eq!(hello.data().unwrap().hello, "world");
+  let post = results.next().unwrap().into_typed::<Post>().unwrap();
+  assert!(post.data().unwrap().post.is_none());
+  assert_eq!(post.errors()[0].message(), "Post not found");
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+  assert_eq!(
+    body,
+    json!([
+      { "query": "{ hello }", "variables": null },
+      {
+        "query": "query Post($id: ID!) { post(id: $id) { title } }",
+        "operationName": "Post",
+        "variables": { "id": 1 }
+      }
+    ])
+  );
+}
+
+#[tokio::test]
+async fn reports_servers_without_batching() {
+  let server = serve(|_| {
+    Response::json(
+      400,
+      r#"{"errors": [{"message": "Operation batching is disabled"}]}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client
+    .batch(vec![
+      GraphQLRequest::new("{ hello }"),
+      GraphQLRequest::new("{ hello }"),
+    ])
+    .await
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::HttpStatus(400));
+  assert!(error.contains_error_message("Operation batching is disabled"));
+}
+
+#[tokio::test]
+async fn checks_the_number_of_results() {
+  let server = serve(|_| Response::json(200, r#"[{"data": {"hello": "world"}}]"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client
+    .batch(vec![
+      GraphQLRequest::new("{ hello }"),
+      GraphQLRequest::new("{ hello }"),
+    ])
+    .await
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Deserialize);
+}
+
+#[tokio::test]
+async fn sends_nothing_without_operations() {
+  let server = serve(|_| Response::json(200, "[]")).await;
+  let client = Client::new(&server.endpoint);
+
+  let results = client.batch(Vec::new()).await.unwrap();
+  assert!(results.is_empty());
+  assert!(server.requests().is_empty());
+}
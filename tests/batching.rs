@@ -0,0 +1,168 @@
+#![cfg(feature = "batching")]
+
+mod server;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::server::{serve, Request, Response};
+use gql_client::{
+  async_trait, Batching, Client, ClientConfig, GraphQLError, Transport, TransportRequest,
+  TransportResponse,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const QUERY: &str = "query Hello($name: String) { hello(name: $name) }";
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+/// Answer each operation with the name it is given
+fn echo(request: &Request) -> Response {
+  Response::json(200, results(&request.body))
+}
+
+fn results(body: &[u8]) -> String {
+  let result = |operation: &Value| json!({ "data": { "hello": operation["variables"]["name"] } });
+  let body: Value = serde_json::from_slice(body).unwrap();
+  let results = match &body {
+    Value::Array(operations) => Value::Array(operations.iter().map(result).collect()),
+    operation => result(operation),
+  };
+  results.to_string()
+}
+
+fn client(endpoint: String, window: u64, max_size: usize) -> Client {
+  Client::new_with_config(ClientConfig::new(endpoint).with_batching(Batching { window, max_size }))
+}
+
+async fn hello(client: &Client, name: &str) -> String {
+  client
+    .query_with_vars_unwrap::<Hello, _>(QUERY, json!({ "name": name }))
+    .await
+    .unwrap()
+    .hello
+}
+
+fn is_batch(request: &Request) -> bool {
+  serde_json::from_slice::<Value>(&request.body)
+    .unwrap()
+    .is_array()
+}
+
+#[tokio::test]
+async fn batches_concurrent_queries() {
+  let server = serve(echo).await;
+  let client = client(server.endpoint.clone(), 50, 10);
+  let clone = client.clone();
+
+  let (a, b, c) = tokio::join!(hello(&client, "a"), hello(&clone, "b"), hello(&client, "c"));
+  assert_eq!((a.as_str(), b.as_str(), c.as_str()), ("a", "b", "c"));
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+  assert_eq!(body.as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn sends_full_batches_without_waiting() {
+  let server = serve(echo).await;
+  let client = client(server.endpoint.clone(), 50, 2);
+
+  let (a, b, c) = tokio::join!(
+    hello(&client, "a"),
+    hello(&client, "b"),
+    hello(&client, "c")
+  );
+  assert_eq!((a.as_str(), b.as_str(), c.as_str()), ("a", "b", "c"));
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 2);
+  assert!(is_batch(&requests[0]));
+  // the last query is alone in its batch
+  assert!(!is_batch(&requests[1]));
+}
+
+#[tokio::test]
+async fn sends_batches_once_when_callers_are_dropped() {
+  let server = serve(echo).await;
+  let client = client(server.endpoint.clone(), 100, 10);
+
+  let (first, second) = tokio::join!(
+    tokio::time::timeout(Duration::from_millis(10), hello(&client, "a")),
+    hello(&client, "b")
+  );
+  assert!(first.is_err());
+  assert_eq!(second, "b");
+
+  // the batch is neither cancelled with the first caller nor sent again
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+  assert_eq!(body.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn does_not_batch_mutations() {
+  let server = serve(echo).await;
+  let client = client(server.endpoint.clone(), 50, 10);
+  let mutation = |name: &'static str| {
+    client.query_with_vars_unwrap::<Hello, _>(
+      "mutation Hello($name: String) { hello(name: $name) }",
+      json!({ "name": name }),
+    )
+  };
+
+  let (a, b) = tokio::join!(mutation("a"), mutation("b"));
+  assert_eq!(a.unwrap().hello, "a");
+  assert_eq!(b.unwrap().hello, "b");
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 2);
+  assert!(!requests.iter().any(is_batch));
+}
+
+/// Transport echoing operations without any async runtime
+#[derive(Clone, Default)]
+struct EchoTransport {
+  requests: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+#[async_trait]
+impl Transport for EchoTransport {
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    let body = results(&request.body);
+    self.requests.lock().unwrap().push(request);
+    Ok(TransportResponse::new(200, HashMap::new(), body))
+  }
+}
+
+#[test]
+fn sends_batches_from_the_caller_outside_of_tokio() {
+  let transport = EchoTransport::default();
+  let config = ClientConfig::new("http://localhost/graphql").with_batching(Batching {
+    window: 20,
+    max_size: 10,
+  });
+  let client = Client::new_with_transport(config, transport.clone());
+
+  let (a, b) = futures::executor::block_on(async {
+    futures::join!(
+      client.query_with_vars_unwrap::<Hello, _>(QUERY, json!({ "name": "a" })),
+      client.query_with_vars_unwrap::<Hello, _>(QUERY, json!({ "name": "b" }))
+    )
+  });
+  assert_eq!(a.unwrap().hello, "a");
+  assert_eq!(b.unwrap().hello, "b");
+
+  let requests = transport.requests.lock().unwrap();
+  assert_eq!(requests.len(), 1);
+  let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+  assert_eq!(body.as_array().unwrap().len(), 2);
+}
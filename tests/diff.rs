@@ -0,0 +1,235 @@
+use gql_client::{ChangeKind, Criticality, Schema};
+
+const OLD: &str = r#"
+type Query {
+  user(id: ID!): User
+  users(first: Int = 10, role: Role): [User!]!
+  search(text: String!): [SearchResult!]!
+  legacy: String
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID!
+  name: String!
+  email: String
+  age: Int
+  role: Role
+}
+
+type Group {
+  id: ID!
+}
+
+union SearchResult = User | Group
+
+enum Role {
+  ADMIN
+  GUEST
+  MEMBER
+}
+
+input UserFilter {
+  name: String
+  roles: [Role!]!
+}
+
+scalar DateTime
+
+directive @cacheControl(maxAge: Int) on FIELD_DEFINITION | OBJECT
+"#;
+
+const NEW: &str = r#"
+type Query {
+  user(id: ID): User!
+  users(first: Int = 20, role: Role, after: String, tenant: ID!): [User!]!
+  search(text: String!): [SearchResult!]!
+  legacy: String @deprecated(reason: "Use search")
+}
+
+interface Node {
+  id: ID!
+}
+
+type User {
+  id: ID!
+  name: String
+  email: [String]
+  role: Role
+  createdAt: DateTime
+}
+
+type Group implements Node {
+  id: ID!
+}
+
+type Team {
+  id: ID!
+}
+
+union SearchResult = User | Team
+
+enum Role {
+  ADMIN
+  MEMBER
+  OWNER
+}
+
+input UserFilter {
+  name: String
+  roles: [Role!]
+  tenant: ID!
+  limit: Int
+}
+
+interface DateTime {
+  id: ID!
+}
+
+directive @cacheControl(maxAge: Int, scope: String) on FIELD_DEFINITION
+
+directive @auth on FIELD_DEFINITION
+"#;
+
+fn diff() -> gql_client::SchemaDiff {
+  let old = Schema::from_sdl(OLD).unwrap();
+  let new = Schema::from_sdl(NEW).unwrap();
+  old.diff(&new)
+}
+
+fn change(kind: ChangeKind, path: &str) -> Criticality {
+  let diff = diff();
+  let changes: Vec<_> = diff
+    .changes
+    .iter()
+    .filter(|change| change.kind == kind && change.path == path)
+    .collect();
+  assert_eq!(changes.len(), 1, "{:?} {} in\n{}", kind, path, diff);
+  changes[0].criticality
+}
+
+#[test]
+fn classifies_type_changes() {
+  use ChangeKind::*;
+  use Criticality::*;
+
+  assert_eq!(change(TypeAdded, "Team"), Safe);
+  assert_eq!(change(TypeKindChanged, "DateTime"), Breaking);
+  assert_eq!(change(InterfaceRemoved, "User"), Breaking);
+  assert_eq!(change(InterfaceAdded, "Group"), Dangerous);
+  assert_eq!(change(UnionMemberRemoved, "SearchResult"), Breaking);
+  assert_eq!(change(UnionMemberAdded, "SearchResult"), Dangerous);
+  assert_eq!(change(EnumValueRemoved, "Role.GUEST"), Breaking);
+  assert_eq!(change(EnumValueAdded, "Role.OWNER"), Dangerous);
+}
+
+#[test]
+fn classifies_field_changes() {
+  use ChangeKind::*;
+  use Criticality::*;
+
+  assert_eq!(change(FieldRemoved, "User.age"), Breaking);
+  assert_eq!(change(FieldAdded, "User.createdAt"), Safe);
+  assert_eq!(change(FieldTypeChanged, "Query.user"), Safe);
+  assert_eq!(change(FieldTypeChanged, "User.name"), Breaking);
+  assert_eq!(change(FieldTypeChanged, "User.email"), Breaking);
+  assert_eq!(change(FieldDeprecationChanged, "Query.legacy"), Safe);
+
+  assert_eq!(change(InputFieldTypeChanged, "UserFilter.roles"), Safe);
+  assert_eq!(change(InputFieldAdded, "UserFilter.tenant"), Breaking);
+  assert_eq!(change(InputFieldAdded, "UserFilter.limit"), Dangerous);
+}
+
+#[test]
+fn classifies_argument_changes() {
+  use ChangeKind::*;
+  use Criticality::*;
+
+  assert_eq!(change(ArgumentTypeChanged, "Query.user(id:)"), Safe);
+  assert_eq!(
+    change(ArgumentDefaultChanged, "Query.users(first:)"),
+    Dangerous
+  );
+  assert_eq!(change(ArgumentAdded, "Query.users(after:)"), Dangerous);
+  assert_eq!(change(ArgumentAdded, "Query.users(tenant:)"), Breaking);
+
+  assert_eq!(change(DirectiveAdded, "@auth"), Safe);
+  assert_eq!(change(ArgumentAdded, "@cacheControl(scope:)"), Dangerous);
+  assert_eq!(change(DirectiveLocationRemoved, "@cacheControl"), Breaking);
+}
+
+#[test]
+fn reports_every_change_once() {
+  let diff = diff();
+  assert!(diff.has_breaking_changes());
+  assert_eq!(diff.changes.len(), 24);
+  assert_eq!(diff.breaking().count(), 10);
+  assert_eq!(diff.dangerous().count(), 7);
+  assert_eq!(diff.safe().count(), 7);
+
+  let change = diff
+    .changes
+    .iter()
+    .find(|change| change.path == "User.name")
+    .unwrap();
+  assert_eq!(
+    change.to_string(),
+    "[breaking] Field User.name changed type from String! to String"
+  );
+}
+
+#[test]
+fn finds_no_changes_between_introspection_and_sdl() {
+  let schema = Schema::from_sdl(OLD).unwrap();
+  let json = serde_json::to_string(&schema).unwrap();
+  let introspected: Schema = serde_json::from_str(&json).unwrap();
+  let printed = Schema::from_sdl(&introspected.to_sdl()).unwrap();
+
+  assert!(schema.diff(&printed).is_empty());
+  assert!(printed.diff(&schema).is_empty());
+}
+
+#[test]
+fn reports_root_type_changes() {
+  let old = Schema::from_sdl("type Query { a: Int }").unwrap();
+  let new = Schema::from_sdl(
+    "schema { query: Root mutation: Mutation } type Root { a: Int } type Mutation { b: Int }",
+  )
+  .unwrap();
+  let diff = old.diff(&new);
+  let roots: Vec<_> = diff
+    .changes
+    .iter()
+    .filter(|change| change.kind == ChangeKind::RootTypeChanged)
+    .map(|change| (change.path.as_str(), change.criticality))
+    .collect();
+  assert_eq!(
+    roots,
+    [
+      ("query", Criticality::Breaking),
+      ("mutation", Criticality::Safe)
+    ]
+  );
+}
+
+#[test]
+fn classifies_repeatable_changes() {
+  let repeatable =
+    Schema::from_sdl("type Query { a: Int } directive @tag repeatable on FIELD").unwrap();
+  let single = Schema::from_sdl("type Query { a: Int } directive @tag on FIELD").unwrap();
+
+  let diff = repeatable.diff(&single);
+  assert_eq!(diff.changes.len(), 1);
+  assert_eq!(diff.changes[0].kind, ChangeKind::DirectiveRepeatableChanged);
+  assert_eq!(
+    diff.changes[0].to_string(),
+    "[breaking] Directive @tag is no longer repeatable"
+  );
+
+  let diff = single.diff(&repeatable);
+  assert_eq!(diff.changes.len(), 1);
+  assert_eq!(diff.changes[0].criticality, Criticality::Safe);
+}
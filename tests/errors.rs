@@ -1,7 +1,15 @@
+mod server;
 mod structs;
 
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::server::{serve, Response};
 use crate::structs::{inputs::SinglePostVariables, SinglePost};
-use gql_client::Client;
+use gql_client::{
+  Client, ClientConfig, GraphQLErrorKind, GraphQLErrorLocation, GraphQLErrorPathParam,
+};
 
 // Initialize endpoint
 const ENDPOINT: &'static str = "https://graphqlzero.almansi.me/api";
@@ -30,3 +38,209 @@ pub async fn properly_parses_json_errors() {
   let err_json = err_data.json().map(|v| v.len()).unwrap_or_default();
   assert!(err_json > 0usize);
 }
+
+#[tokio::test]
+pub async fn returns_partial_data_along_with_errors() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{
+        "data": {"post": {"id": "2"}, "author": null},
+        "errors": [{"message": "Author unavailable", "path": ["author"]}]
+      }"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+  let query = "query { post(id: 2) { id } author { id } }";
+
+  let response = client.query_response::<SinglePost>(query).await.unwrap();
+  assert!(response.has_errors());
+  assert_eq!(response.data().unwrap().post.id, "2");
+  assert_eq!(response.errors().len(), 1);
+
+  let error = client.query::<SinglePost>(query).await.unwrap_err();
+  assert!(error.contains_error_message("Author unavailable"));
+}
+
+#[tokio::test]
+pub async fn ignores_an_empty_errors_list() {
+  let server =
+    serve(|_| Response::json(200, r#"{"data": {"post": {"id": "2"}}, "errors": []}"#)).await;
+  let client = Client::new(&server.endpoint);
+  let query = "query { post(id: 2) { id } }";
+
+  let response = client.query_response::<SinglePost>(query).await.unwrap();
+  assert!(!response.has_errors());
+  let data = response.into_result().unwrap().unwrap();
+  assert_eq!(data.post.id, "2");
+
+  let data = client.query::<SinglePost>(query).await.unwrap().unwrap();
+  assert_eq!(data.post.id, "2");
+}
+
+#[tokio::test]
+pub async fn reports_error_kinds() {
+  let server = serve(
+    |request| match String::from_utf8_lossy(&request.body).as_ref() {
+      body if body.contains("unauthorized") => {
+        Response::with_headers(401, &[("content-type", "text/html")], "<h1>401</h1>")
+      }
+      body if body.contains("invalid") => Response::json(200, "not json"),
+      _ => Response::json(200, r#"{"errors": [{"message": "Unknown field"}]}"#),
+    },
+  )
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client
+    .query::<SinglePost>("{ unauthorized }")
+    .await
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::HttpStatus(401));
+
+  let error = client.query::<SinglePost>("{ invalid }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Deserialize);
+  let source = error.source().unwrap();
+  assert!(source.downcast_ref::<serde_json::Error>().is_some());
+
+  let error = client.query::<SinglePost>("{ unknown }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::GraphQL);
+
+  let error = Client::new("not a url")
+    .query::<SinglePost>("{ post }")
+    .await
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Config);
+}
+
+#[tokio::test]
+pub async fn reports_timeouts() {
+  let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let endpoint = format!("http://{}/graphql", listener.local_addr().unwrap());
+  tokio::spawn(async move {
+    // accept the connection and never answer
+    let (_socket, _) = listener.accept().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+  });
+
+  let client = Client::new_with_config(ClientConfig::new(endpoint).with_timeout(1));
+  let error = client.query::<SinglePost>("{ post }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Timeout);
+  let source = error.source().unwrap();
+  assert!(source
+    .downcast_ref::<reqwest::Error>()
+    .unwrap()
+    .is_timeout());
+
+  // composes with boxed errors and `?`
+  let boxed: Box<dyn Error + Send + Sync> = error.into();
+  assert!(boxed.source().is_some());
+}
+
+#[tokio::test]
+pub async fn exposes_error_message_fields() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{"data": null, "errors": [{
+        "message": "Not logged in",
+        "locations": [{"line": 1, "column": 3}],
+        "path": ["posts", 1, "author"],
+        "extensions": {"code": "UNAUTHENTICATED"}
+      }]}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client
+    .query::<SinglePost>("{ posts { author } }")
+    .await
+    .unwrap_err();
+  assert!(error.contains_extension_code("UNAUTHENTICATED"));
+
+  let message = &error.errors()[0];
+  assert_eq!(message.message(), "Not logged in");
+  assert_eq!(message.extension_code(), Some("UNAUTHENTICATED"));
+  assert_eq!(
+    message.locations(),
+    &[GraphQLErrorLocation { line: 1, column: 3 }]
+  );
+  assert_eq!(
+    message.path(),
+    &[
+      GraphQLErrorPathParam::String("posts".to_string()),
+      GraphQLErrorPathParam::Number(1),
+      GraphQLErrorPathParam::String("author".to_string()),
+    ]
+  );
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ValidationExtensions {
+  code: String,
+  invalid_fields: Vec<String>,
+}
+
+#[tokio::test]
+pub async fn deserializes_typed_error_extensions() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{"data": null, "errors": [
+        {"message": "Invalid input", "extensions": {"code": "BAD_USER_INPUT", "invalidFields": ["title"]}},
+        {"message": "No extensions"}
+      ]}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client
+    .query::<SinglePost>("{ post { title } }")
+    .await
+    .unwrap_err();
+
+  let errors = error.errors_as::<ValidationExtensions>().unwrap();
+  assert_eq!(errors[0].message(), "Invalid input");
+  assert_eq!(
+    errors[0].extensions(),
+    Some(&ValidationExtensions {
+      code: "BAD_USER_INPUT".to_string(),
+      invalid_fields: vec!["title".to_string()],
+    })
+  );
+  assert_eq!(errors[1].extensions(), None);
+
+  let error = error.errors_as::<Vec<String>>().unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Deserialize);
+}
+
+#[tokio::test]
+pub async fn renders_query_snippets() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{"data": null, "errors": [{
+        "message": "Cannot query field \"name\" on type \"User\".",
+        "locations": [{"line": 4, "column": 7}],
+        "path": ["posts", 2, "author"]
+      }]}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let query = "query {\n  posts {\n    author {\n      name\n    }\n  }\n}";
+  let error = client.query::<SinglePost>(query).await.unwrap_err();
+  assert_eq!(error.query(), Some(query));
+
+  let rendered = error.to_string();
+  assert!(
+    rendered.contains("  --> 4:7\n   |\n 4 |       name\n   |       ^\nPath: posts[2].author\n"),
+    "{}",
+    rendered
+  );
+}
@@ -0,0 +1,94 @@
+mod server;
+
+use std::collections::HashMap;
+
+use crate::server::{serve, Response};
+use gql_client::{Client, ClientConfig, HttpMethod, RequestOptions};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+#[tokio::test]
+async fn sends_queries_with_get() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client =
+    Client::new_with_config(ClientConfig::new(&server.endpoint).with_query_method(HttpMethod::Get));
+
+  let data = client
+    .query_with_vars_unwrap::<Hello, _>(
+      "query Hello($name: String) { hello(name: $name) }",
+      json!({ "name": "world" }),
+    )
+    .await
+    .unwrap();
+  assert_eq!(data.hello, "world");
+  client
+    .query_unwrap::<Hello>("mutation { hello }")
+    .await
+    .unwrap();
+
+  let requests = server.requests();
+  assert_eq!(requests[0].method, "GET");
+  assert!(requests[0].body.is_empty());
+  let url = Url::parse(&format!("http://localhost{}", requests[0].target)).unwrap();
+  let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+  assert_eq!(
+    params["query"],
+    "query Hello($name: String) { hello(name: $name) }"
+  );
+  assert_eq!(
+    serde_json::from_str::<Value>(&params["variables"]).unwrap(),
+    json!({ "name": "world" })
+  );
+
+  assert_eq!(requests[1].method, "POST");
+}
+
+#[tokio::test]
+async fn selects_the_method_per_request() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let response = client
+    .query_with_options::<Hello, _>(
+      "{ hello }",
+      (),
+      RequestOptions::default().with_method(HttpMethod::Get),
+    )
+    .await
+    .unwrap();
+  assert_eq!(response.data().unwrap().hello, "world");
+  client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+
+  let requests = server.requests();
+  assert_eq!(requests[0].method, "GET");
+  assert_eq!(requests[0].target, "/graphql?query=%7B+hello+%7D");
+  assert_eq!(requests[1].method, "POST");
+}
+
+#[tokio::test]
+async fn finds_mutations_after_invalid_tokens() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client =
+    Client::new_with_config(ClientConfig::new(&server.endpoint).with_query_method(HttpMethod::Get));
+
+  client
+    .query_unwrap::<Hello>("query Hello { hello % }\nmutation Greet { hello }")
+    .await
+    .unwrap();
+  client
+    .query_unwrap::<Hello>("% mutation Greet { hello }")
+    .await
+    .unwrap();
+
+  let requests = server.requests();
+  assert_eq!(requests[0].method, "POST");
+  assert_eq!(requests[1].method, "POST");
+  let body: Value = serde_json::from_slice(&requests[1].body).unwrap();
+  assert_eq!(body["operationName"], "Greet");
+}
@@ -0,0 +1,167 @@
+mod server;
+
+use futures_util::StreamExt;
+use gql_client::{Client, IncrementalPayload, IncrementalResult};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::server::{serve, Response};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+  user: User,
+}
+
+#[derive(Deserialize, Debug)]
+struct User {
+  name: String,
+  bio: Option<String>,
+  friends: Vec<Friend>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Friend {
+  id: String,
+}
+
+const QUERY: &str = r#"
+  query {
+    user {
+      name
+      friends @stream(initialCount: 1) { id }
+      ... @defer(label: "bio") { bio }
+    }
+  }
+"#;
+
+fn part(payload: serde_json::Value) -> String {
+  format!(
+    "\r\n---\r\ncontent-type: application/json; charset=utf-8\r\n\r\n{}",
+    payload
+  )
+}
+
+#[tokio::test]
+async fn streams_and_merges_multipart_payloads() {
+  let server = serve(|_| {
+    let initial = part(json!({
+      "data": {"user": {"name": "Ann", "friends": [{"id": "1"}]}},
+      "hasNext": true
+    }));
+    let deferred = part(json!({
+      "incremental": [{"data": {"bio": "Hello"}, "path": ["user"], "label": "bio"}],
+      "hasNext": true
+    }));
+    let streamed = part(json!({
+      "incremental": [{"items": [{"id": "2"}, {"id": "3"}], "path": ["user", "friends", 1]}],
+      "hasNext": false
+    }));
+    Response::streamed(
+      "multipart/mixed; boundary=\"-\"; deferSpec=20220824",
+      vec![
+        initial,
+        // a part split in the middle of its json
+        deferred[..40].to_string(),
+        deferred[40..].to_string(),
+        part(json!({})),
+        streamed,
+        "\r\n-----\r\n".to_string(),
+      ],
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let payloads: Vec<IncrementalPayload> = client
+    .query_incremental(QUERY, ())
+    .await
+    .unwrap()
+    .map(|payload| payload.unwrap())
+    .collect()
+    .await;
+  assert_eq!(payloads.len(), 3);
+  let patch = &payloads[1].incremental.as_ref().unwrap()[0];
+  assert_eq!(patch.label.as_deref(), Some("bio"));
+  assert!(!payloads[2].has_next);
+
+  let mut result = IncrementalResult::new();
+  for payload in payloads {
+    result.apply(payload);
+  }
+  assert!(!result.has_next());
+  let data: Data = result.into_data().unwrap();
+  assert_eq!(data.user.name, "Ann");
+  assert_eq!(data.user.bio.as_deref(), Some("Hello"));
+  let ids: Vec<&str> = data.user.friends.iter().map(|f| f.id.as_str()).collect();
+  assert_eq!(ids, vec!["1", "2", "3"]);
+
+  let request = &server.requests()[0];
+  assert!(request.headers["accept"].starts_with("multipart/mixed"));
+}
+
+#[tokio::test]
+async fn accepts_a_single_json_result() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{"data":{"user":{"name":"Ann","bio":null,"friends":[]}}}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let mut stream = client.query_incremental(QUERY, ()).await.unwrap();
+  let mut result = IncrementalResult::new();
+  while let Some(payload) = stream.next().await {
+    result.apply(payload.unwrap());
+  }
+
+  let data: Data = result.into_data().unwrap();
+  assert_eq!(data.user.name, "Ann");
+}
+
+#[test]
+fn returns_errors_of_patches() {
+  let mut result = IncrementalResult::new();
+  result.apply(serde_json::from_value(json!({"data": {"user": {}}, "hasNext": true})).unwrap());
+  result.apply(
+    serde_json::from_value(json!({
+      "incremental": [{
+        "data": null,
+        "path": ["user"],
+        "errors": [{"message": "Bio unavailable", "path": ["user", "bio"]}]
+      }],
+      "hasNext": false
+    }))
+    .unwrap(),
+  );
+
+  assert_eq!(result.errors().len(), 1);
+  let error = result.into_data::<serde_json::Value>().unwrap_err();
+  assert!(error.contains_error_message("Bio unavailable"));
+}
+
+#[tokio::test]
+async fn matches_the_media_type_case_insensitively() {
+  let server = serve(|_| {
+    Response::streamed(
+      "Multipart/Mixed; Boundary=\"-\"",
+      vec![
+        part(json!({"data": {"user": {"name": "Ann", "friends": []}}, "hasNext": false})),
+        "\r\n-----\r\n".to_string(),
+      ],
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let payloads: Vec<IncrementalPayload> = client
+    .query_incremental(QUERY, ())
+    .await
+    .unwrap()
+    .map(|payload| payload.unwrap())
+    .collect()
+    .await;
+  assert_eq!(payloads.len(), 1);
+  assert_eq!(payloads[0].data.as_ref().unwrap()["user"]["name"], "Ann");
+}
@@ -0,0 +1,251 @@
+mod server;
+
+use crate::server::{serve, Response};
+use gql_client::{Client, GraphQLErrorKind, IntrospectionOptions, TypeKind, INTROSPECTION_QUERY};
+use serde_json::{json, Value};
+
+fn non_null(name: &str) -> Value {
+  json!({ "kind": "NON_NULL", "name": null, "ofType": { "kind": "SCALAR", "name": name, "ofType": null } })
+}
+
+fn introspection() -> Value {
+  json!({
+    "data": {
+      "__schema": {
+        "queryType": { "name": "Query" },
+        "mutationType": null,
+        "subscriptionType": null,
+        "types": [
+          {
+            "kind": "OBJECT",
+            "name": "Query",
+            "description": "Root query",
+            "fields": [
+              {
+                "name": "users",
+                "description": null,
+                "args": [
+                  {
+                    "name": "first",
+                    "description": "page size",
+                    "type": { "kind": "SCALAR", "name": "Int", "ofType": null },
+                    "defaultValue": "10"
+                  }
+                ],
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": { "kind": "NON_NULL", "name": null, "ofType": { "kind": "OBJECT", "name": "User", "ofType": null } }
+                  }
+                },
+                "isDeprecated": false,
+                "deprecationReason": null
+              },
+              {
+                "name": "me",
+                "description": null,
+                "args": [],
+                "type": { "kind": "OBJECT", "name": "User", "ofType": null },
+                "isDeprecated": true,
+                "deprecationReason": "Use users"
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "enumValues": null,
+            "possibleTypes": null
+          },
+          {
+            "kind": "OBJECT",
+            "name": "User",
+            "description": null,
+            "fields": [
+              {
+                "name": "id",
+                "description": null,
+                "args": [],
+                "type": non_null("ID"),
+                "isDeprecated": false,
+                "deprecationReason": null
+              },
+              {
+                "name": "role",
+                "description": null,
+                "args": [],
+                "type": { "kind": "ENUM", "name": "Role", "ofType": null },
+                "isDeprecated": false,
+                "deprecationReason": null
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "enumValues": null,
+            "possibleTypes": null
+          },
+          {
+            "kind": "ENUM",
+            "name": "Role",
+            "description": null,
+            "fields": null,
+            "inputFields": null,
+            "interfaces": null,
+            "enumValues": [
+              { "name": "ADMIN", "description": null, "isDeprecated": false, "deprecationReason": null },
+              { "name": "GUEST", "description": null, "isDeprecated": true, "deprecationReason": "No more guests" }
+            ],
+            "possibleTypes": null
+          },
+          {
+            "kind": "SCALAR",
+            "name": "ID",
+            "description": null,
+            "fields": null,
+            "inputFields": null,
+            "interfaces": null,
+            "enumValues": null,
+            "possibleTypes": null
+          }
+        ],
+        "directives": [
+          {
+            "name": "skip",
+            "description": null,
+            "locations": ["FIELD", "FRAGMENT_SPREAD", "INLINE_FRAGMENT"],
+            "args": [
+              { "name": "if", "description": null, "type": non_null("Boolean"), "defaultValue": null }
+            ]
+          }
+        ]
+      }
+    }
+  })
+}
+
+#[tokio::test]
+async fn introspects_the_schema() {
+  let server = serve(|_| Response::json(200, introspection().to_string())).await;
+  let client = Client::new(&server.endpoint);
+
+  let schema = client.introspect().await.unwrap();
+
+  let requests = server.requests();
+  let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+  assert_eq!(body["query"], INTROSPECTION_QUERY);
+  assert_eq!(body["operationName"], "IntrospectionQuery");
+
+  assert_eq!(schema.query_type.as_deref(), Some("Query"));
+  assert_eq!(schema.mutation_type, None);
+  assert_eq!(schema.types.len(), 4);
+
+  let query = schema.get_type("Query").unwrap();
+  assert_eq!(query.kind, TypeKind::Object);
+  assert_eq!(query.description.as_deref(), Some("Root query"));
+  let users = query.get_field("users").unwrap();
+  assert_eq!(users.ty.to_string(), "[User!]!");
+  assert_eq!(users.ty.named_type(), "User");
+  assert_eq!(users.args[0].name, "first");
+  assert_eq!(users.args[0].ty.to_string(), "Int");
+  assert_eq!(users.args[0].default_value.as_deref(), Some("10"));
+  let me = query.get_field("me").unwrap();
+  assert!(me.is_deprecated);
+  assert_eq!(me.deprecation_reason.as_deref(), Some("Use users"));
+
+  let role = schema.get_type("Role").unwrap();
+  assert_eq!(role.kind, TypeKind::Enum);
+  assert!(role.fields.is_empty());
+  assert_eq!(role.enum_values.len(), 2);
+  assert!(role.enum_values[1].is_deprecated);
+
+  assert!(schema.get_type("ID").unwrap().is_builtin_scalar());
+
+  let skip = schema.get_directive("skip").unwrap();
+  assert_eq!(
+    skip.locations,
+    ["FIELD", "FRAGMENT_SPREAD", "INLINE_FRAGMENT"]
+  );
+  assert_eq!(skip.args[0].ty.to_string(), "Boolean!");
+  assert!(!skip.is_repeatable);
+}
+
+/// Server without support for repeatable directives, the `isRepeatable` field fails validation
+async fn server_without_repeatable_directives() -> server::Server {
+  serve(|request| {
+    let body: Value = serde_json::from_slice(&request.body).unwrap();
+    if body["query"].as_str().unwrap().contains("isRepeatable") {
+      return Response::json(
+        400,
+        r#"{"errors":[{"message":"Cannot query field \"isRepeatable\" on type \"__Directive\"."}]}"#,
+      );
+    }
+    Response::json(200, introspection().to_string())
+  })
+  .await
+}
+
+#[tokio::test]
+async fn asks_whether_directives_are_repeatable_only_when_enabled() {
+  let server = server_without_repeatable_directives().await;
+  let client = Client::new(&server.endpoint);
+
+  let schema = client.introspect().await.unwrap();
+  assert!(!schema.directives[0].is_repeatable);
+
+  let options = IntrospectionOptions::default().with_directive_is_repeatable(true);
+  let error = client.introspect_with(options).await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::HttpStatus(400));
+  assert!(
+    error.contains_error_message("Cannot query field \"isRepeatable\" on type \"__Directive\".")
+  );
+}
+
+#[tokio::test]
+async fn introspects_repeatable_directives() {
+  let server = serve(|_| {
+    let mut introspection = introspection();
+    introspection["data"]["__schema"]["directives"][0]["isRepeatable"] = json!(true);
+    Response::json(200, introspection.to_string())
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let options = IntrospectionOptions::default().with_directive_is_repeatable(true);
+  let schema = client.introspect_with(options).await.unwrap();
+  assert!(schema.directives[0].is_repeatable);
+
+  let body: Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+  assert_eq!(body["query"], options.query());
+  assert!(options
+    .query()
+    .contains("description\n      isRepeatable\n      locations"));
+}
+
+#[tokio::test]
+async fn returns_introspection_errors() {
+  let server = serve(|_| {
+    Response::json(
+      200,
+      r#"{"data":null,"errors":[{"message":"GraphQL introspection is not allowed"}]}"#,
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let error = client.introspect().await.unwrap_err();
+  assert!(error.contains_error_message("GraphQL introspection is not allowed"));
+}
+
+#[test]
+fn serializes_schemas_as_introspection_results() {
+  let data = introspection()["data"]["__schema"].clone();
+  let schema: gql_client::Schema = serde_json::from_value(data).unwrap();
+  let json = serde_json::to_value(&schema).unwrap();
+  assert_eq!(json["queryType"], json!({ "name": "Query" }));
+  assert_eq!(json["mutationType"], Value::Null);
+  assert_eq!(
+    serde_json::from_value::<gql_client::Schema>(json).unwrap(),
+    schema
+  );
+}
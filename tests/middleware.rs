@@ -0,0 +1,222 @@
+mod server;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::server::{serve, Response};
+use futures_util::StreamExt;
+use gql_client::{
+  async_trait, Client, GraphQLError, GraphQLErrorKind, Middleware, Next, NextStreaming,
+  TransportRequest, TransportResponse, TransportStreamResponse,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+/// Records the requests and responses it sees in a shared log
+struct Recorder {
+  name: &'static str,
+  log: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Recorder {
+  async fn handle(
+    &self,
+    mut request: TransportRequest,
+    next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError> {
+    self
+      .log
+      .lock()
+      .unwrap()
+      .push(format!("{} request", self.name));
+    request
+      .headers
+      .insert(format!("x-{}", self.name), "1".to_string());
+    let response = next.run(request).await?;
+    self
+      .log
+      .lock()
+      .unwrap()
+      .push(format!("{} response {}", self.name, response.status));
+    Ok(response)
+  }
+
+  async fn handle_streaming(
+    &self,
+    request: TransportRequest,
+    next: NextStreaming<'_>,
+  ) -> Result<TransportStreamResponse, GraphQLError> {
+    let response = next.run(request).await?;
+    self.log.lock().unwrap().push(format!(
+      "{} stream {}",
+      self.name,
+      response.header("content-type").unwrap_or_default()
+    ));
+    Ok(response)
+  }
+}
+
+/// Sends requests to another endpoint and rewrites the response
+struct Rewrite {
+  endpoint: String,
+}
+
+#[async_trait]
+impl Middleware for Rewrite {
+  async fn handle(
+    &self,
+    mut request: TransportRequest,
+    next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError> {
+    request.url = self.endpoint.clone();
+    let mut response = next.run(request).await?;
+    response.body = response.body.replace("world", "middleware");
+    Ok(response)
+  }
+}
+
+struct Reject;
+
+#[async_trait]
+impl Middleware for Reject {
+  async fn handle(
+    &self,
+    _request: TransportRequest,
+    _next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError> {
+    Err(GraphQLError::with_kind(
+      GraphQLErrorKind::Config,
+      "Missing credentials",
+    ))
+  }
+}
+
+/// Measures how long each request takes
+struct Timing {
+  durations: Arc<Mutex<Vec<Duration>>>,
+}
+
+#[async_trait]
+impl Middleware for Timing {
+  async fn handle(
+    &self,
+    request: TransportRequest,
+    next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError> {
+    let started = Instant::now();
+    let response = next.run(request).await;
+    self.durations.lock().unwrap().push(started.elapsed());
+    response
+  }
+}
+
+/// Holds requests back before passing them on
+struct Delay(Duration);
+
+#[async_trait]
+impl Middleware for Delay {
+  async fn handle(
+    &self,
+    request: TransportRequest,
+    next: Next<'_>,
+  ) -> Result<TransportResponse, GraphQLError> {
+    tokio::time::sleep(self.0).await;
+    next.run(request).await
+  }
+}
+
+#[tokio::test]
+async fn runs_middleware_in_order() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let client = Client::new("http://localhost:1/unused")
+    .with_middleware(Rewrite {
+      endpoint: server.endpoint.clone(),
+    })
+    .with_middleware(Recorder {
+      name: "first",
+      log: log.clone(),
+    })
+    .with_middleware(Recorder {
+      name: "second",
+      log: log.clone(),
+    });
+
+  let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  assert_eq!(data.hello, "middleware");
+
+  assert_eq!(
+    *log.lock().unwrap(),
+    vec![
+      "first request",
+      "second request",
+      "second response 200",
+      "first response 200"
+    ]
+  );
+  let request = &server.requests()[0];
+  assert_eq!(request.headers["x-first"], "1");
+  assert_eq!(request.headers["x-second"], "1");
+}
+
+#[tokio::test]
+async fn stops_on_middleware_errors() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = Client::new(&server.endpoint).with_middleware(Reject);
+
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Config);
+  assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn measures_request_durations() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let durations = Arc::new(Mutex::new(Vec::new()));
+  let client = Client::new(&server.endpoint)
+    .with_middleware(Timing {
+      durations: durations.clone(),
+    })
+    .with_middleware(Delay(Duration::from_millis(50)));
+
+  client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+
+  let durations = durations.lock().unwrap();
+  assert_eq!(durations.len(), 2);
+  for duration in durations.iter() {
+    assert!(*duration >= Duration::from_millis(50), "{:?}", duration);
+    assert!(*duration < Duration::from_secs(5), "{:?}", duration);
+  }
+}
+
+#[tokio::test]
+async fn runs_middleware_around_streamed_responses() {
+  let server = serve(|_| {
+    Response::streamed(
+      "text/event-stream",
+      vec!["event: next\ndata: {\"data\":{\"hello\":\"world\"}}\n\n".to_string()],
+    )
+  })
+  .await;
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let client = Client::new(&server.endpoint).with_middleware(Recorder {
+    name: "first",
+    log: log.clone(),
+  });
+
+  let items: Vec<_> = client
+    .subscribe_sse::<Hello>("subscription { hello }")
+    .await
+    .unwrap()
+    .collect()
+    .await;
+
+  assert_eq!(items[0].as_ref().unwrap().hello, "world");
+  assert_eq!(*log.lock().unwrap(), vec!["first stream text/event-stream"]);
+}
@@ -0,0 +1,107 @@
+mod server;
+
+use crate::server::{serve, Response};
+use gql_client::{Client, ClientConfig, DocumentIdField, GraphQLErrorKind, PersistedDocuments};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+const MANIFEST: &str = r#"{
+  "hello-v1": "query Hello($name: String) { hello(name: $name) }"
+}"#;
+
+fn client(endpoint: String, id_field: DocumentIdField) -> Client {
+  let documents = PersistedDocuments::from_manifest(MANIFEST)
+    .unwrap()
+    .with_id_field(id_field);
+  Client::new_with_config(ClientConfig::new(endpoint).with_persisted_documents(documents))
+}
+
+#[tokio::test]
+async fn sends_registered_operations_by_id() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = client(server.endpoint.clone(), DocumentIdField::DocumentId);
+
+  let data = client
+    .query_with_vars_unwrap::<Hello, _>(
+      "query Hello($name: String) { hello(name: $name) }\n",
+      json!({ "name": "world" }),
+    )
+    .await
+    .unwrap();
+  assert_eq!(data.hello, "world");
+
+  let body: Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+  assert_eq!(
+    body,
+    json!({
+      "documentId": "hello-v1",
+      "operationName": "Hello",
+      "variables": { "name": "world" }
+    })
+  );
+}
+
+#[tokio::test]
+async fn sends_ids_in_the_configured_field() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = client(server.endpoint.clone(), DocumentIdField::Id);
+
+  client
+    .query_with_vars_unwrap::<Hello, _>(
+      "query Hello($name: String) { hello(name: $name) }",
+      json!({ "name": "world" }),
+    )
+    .await
+    .unwrap();
+
+  let body: Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+  assert_eq!(body["id"], "hello-v1");
+  assert!(body.get("documentId").is_none());
+  assert!(body.get("query").is_none());
+}
+
+#[tokio::test]
+async fn rejects_unregistered_operations_locally() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = client(server.endpoint.clone(), DocumentIdField::DocumentId);
+
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Config);
+  assert_eq!(error.query(), Some("{ hello }"));
+  assert!(server.requests().is_empty());
+}
+
+#[test]
+fn rejects_manifests_with_duplicate_documents() {
+  let error = PersistedDocuments::from_manifest(
+    r#"{ "b": "{ hello }", "a": "  { hello }\n", "c": "{ other }" }"#,
+  )
+  .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Config);
+  assert_eq!(
+    error.message(),
+    "Operations a and b of the manifest have the same document"
+  );
+}
+
+#[test]
+fn indexes_manifests_of_deserialized_configs() {
+  let documents: PersistedDocuments = serde_json::from_value(json!({
+    "manifest": { "hello-v1": "{ hello }" },
+    "id_field": "Id"
+  }))
+  .unwrap();
+  assert_eq!(documents.id_of(" { hello } "), Some("hello-v1"));
+  assert_eq!(documents.id_field, DocumentIdField::Id);
+
+  let error = serde_json::from_value::<PersistedDocuments>(json!({
+    "manifest": { "a": "{ hello }", "b": "{ hello }" }
+  }))
+  .unwrap_err();
+  assert!(error.to_string().contains("same document"));
+}
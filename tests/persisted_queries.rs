@@ -0,0 +1,109 @@
+mod server;
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::server::{serve, Request, Response};
+use gql_client::{Client, ClientConfig, PersistedQueries};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+
+const HELLO_HASH: &str = "001c3174e099bd72b729d0c0a529ba9f5a740c446e2a6e1d71b283cb84ec3065";
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+/// Request payload, from the json body or the url parameters of a GET request
+fn payload(request: &Request) -> Value {
+  if request.method == "POST" {
+    return serde_json::from_slice(&request.body).unwrap();
+  }
+  let url = Url::parse(&format!("http://localhost{}", request.target)).unwrap();
+  let mut payload = serde_json::Map::new();
+  for (name, value) in url.query_pairs() {
+    let value = match name.as_ref() {
+      "query" => Value::String(value.into_owned()),
+      _ => serde_json::from_str(&value).unwrap(),
+    };
+    payload.insert(name.into_owned(), value);
+  }
+  Value::Object(payload)
+}
+
+/// Server storing queries by hash, like Apollo Server
+async fn apq_server() -> server::Server {
+  let known = Mutex::new(HashSet::new());
+  serve(move |request| {
+    let payload = payload(request);
+    let hash = payload["extensions"]["persistedQuery"]["sha256Hash"]
+      .as_str()
+      .unwrap()
+      .to_string();
+    let mut known = known.lock().unwrap();
+    if payload.get("query").is_some() {
+      known.insert(hash);
+    } else if !known.contains(&hash) {
+      return Response::json(
+        200,
+        r#"{"errors":[{"message":"PersistedQueryNotFound","extensions":{"code":"PERSISTED_QUERY_NOT_FOUND"}}]}"#,
+      );
+    }
+    Response::json(200, r#"{"data":{"hello":"world"}}"#)
+  })
+  .await
+}
+
+fn client(endpoint: String, use_get: bool) -> Client {
+  Client::new_with_config(
+    ClientConfig::new(endpoint).with_persisted_queries(PersistedQueries { use_get }),
+  )
+}
+
+#[tokio::test]
+async fn sends_the_full_query_only_when_unknown() {
+  let server = apq_server().await;
+  let client = client(server.endpoint.clone(), false);
+
+  for _ in 0..2 {
+    let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+    assert_eq!(data.hello, "world");
+  }
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 3);
+  let payloads: Vec<Value> = requests.iter().map(payload).collect();
+  assert_eq!(
+    payloads[0]["extensions"]["persistedQuery"]["sha256Hash"],
+    HELLO_HASH
+  );
+  assert!(payloads[0].get("query").is_none());
+  assert_eq!(payloads[1]["query"], "{ hello }");
+  assert!(payloads[2].get("query").is_none());
+}
+
+#[tokio::test]
+async fn sends_hashed_queries_with_get() {
+  let server = apq_server().await;
+  let client = client(server.endpoint.clone(), true);
+
+  for _ in 0..2 {
+    client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  }
+  client
+    .query_unwrap::<Hello>("mutation { hello }")
+    .await
+    .unwrap();
+
+  let methods: Vec<String> = server
+    .requests()
+    .into_iter()
+    .map(|request| request.method)
+    .collect();
+  assert_eq!(methods, vec!["GET", "POST", "GET", "POST", "POST"]);
+  let request = &server.requests()[0];
+  assert!(request.target.starts_with("/graphql?"));
+  assert!(!request.headers.contains_key("content-type"));
+}
@@ -0,0 +1,67 @@
+mod server;
+
+use crate::server::{serve, Response};
+use gql_client::{Client, RequestOptions};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+fn body(server: &server::Server, index: usize) -> Value {
+  serde_json::from_slice(&server.requests()[index].body).unwrap()
+}
+
+#[tokio::test]
+async fn detects_the_name_of_a_single_operation() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  client
+    .query_unwrap::<Hello>("# greeting\nquery Hello { hello }")
+    .await
+    .unwrap();
+  client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  client
+    .query_unwrap::<Hello>("query Hello { ...Greeting } fragment Greeting on Query { hello }")
+    .await
+    .unwrap();
+
+  assert_eq!(body(&server, 0)["operationName"], "Hello");
+  assert!(body(&server, 1).get("operationName").is_none());
+  assert_eq!(body(&server, 2)["operationName"], "Hello");
+}
+
+#[tokio::test]
+async fn sends_operation_name_and_extensions() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = Client::new(&server.endpoint);
+  let document = "query Hello { hello } query Bye { bye }";
+
+  let response = client
+    .query_with_options::<Hello, _>(
+      document,
+      (),
+      RequestOptions::default()
+        .with_operation_name("Hello")
+        .with_extension("trace", json!({ "id": "abc" })),
+    )
+    .await
+    .unwrap();
+  assert_eq!(response.data().unwrap().hello, "world");
+  client.query::<Hello>(document).await.unwrap();
+
+  assert_eq!(
+    body(&server, 0),
+    json!({
+      "query": document,
+      "operationName": "Hello",
+      "variables": null,
+      "extensions": { "trace": { "id": "abc" } }
+    })
+  );
+  // the server picks the operation, or fails, when several are defined
+  assert!(body(&server, 1).get("operationName").is_none());
+}
@@ -1,6 +1,12 @@
+mod server;
+
 use reqwest::Url;
 use std::str::FromStr;
 
+use crate::server::{serve, Response};
+use gql_client::{Client, ClientConfig};
+use serde::Deserialize;
+
 #[test]
 fn test_url() {
   let url_raw = "https://subql.darwinia.network/subql-bridger-darwinia";
@@ -12,3 +18,40 @@ fn test_url() {
     format!("{}://{}", schema, host.to_string())
   );
 }
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+#[tokio::test]
+async fn reuses_connections_across_queries_and_clones() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  for _ in 0..3 {
+    let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+    assert_eq!(data.hello, "world");
+  }
+  let data = client
+    .clone()
+    .query_unwrap::<Hello>("{ hello }")
+    .await
+    .unwrap();
+  assert_eq!(data.hello, "world");
+
+  assert_eq!(server.connections(), 1);
+}
+
+#[tokio::test]
+async fn uses_caller_supplied_client() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let reqwest_client = reqwest::Client::new();
+  let first = Client::new_with_client(ClientConfig::new(&server.endpoint), reqwest_client.clone());
+  let second = Client::new_with_client(ClientConfig::new(&server.endpoint), reqwest_client);
+
+  first.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  second.query_unwrap::<Hello>("{ hello }").await.unwrap();
+
+  assert_eq!(server.connections(), 1);
+}
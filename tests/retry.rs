@@ -0,0 +1,169 @@
+mod server;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::server::{serve, Response};
+use gql_client::{Client, ClientConfig, GraphQLErrorKind, RetryPolicy};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+fn retrying_client(endpoint: String) -> Client {
+  Client::new_with_config(ClientConfig::new(endpoint).with_retry(RetryPolicy {
+    base_delay: 1,
+    max_delay: 50,
+    ..Default::default()
+  }))
+}
+
+/// Fail with a 503 until `failures` requests have been received
+async fn flaky_server(failures: usize) -> server::Server {
+  let received = Arc::new(AtomicUsize::new(0));
+  serve(move |_| {
+    if received.fetch_add(1, Ordering::SeqCst) < failures {
+      Response::with_headers(503, &[("retry-after", "0")], "Service Unavailable")
+    } else {
+      Response::json(200, r#"{"data":{"hello":"world"}}"#)
+    }
+  })
+  .await
+}
+
+/// Fail once with a 503 asking to retry after `retry_after`, returns the times requests were received
+async fn retry_after_server(
+  retry_after: impl Fn() -> String + Send + Sync + 'static,
+) -> (server::Server, Arc<Mutex<Vec<Instant>>>) {
+  let received = Arc::new(Mutex::new(Vec::new()));
+  let times = received.clone();
+  let server = serve(move |_| {
+    let mut times = times.lock().unwrap();
+    times.push(Instant::now());
+    if times.len() == 1 {
+      Response::with_headers(
+        503,
+        &[("retry-after", &retry_after())],
+        "Service Unavailable",
+      )
+    } else {
+      Response::json(200, r#"{"data":{"hello":"world"}}"#)
+    }
+  })
+  .await;
+  (server, received)
+}
+
+#[tokio::test]
+async fn retries_transient_statuses() {
+  let server = flaky_server(2).await;
+  let client = retrying_client(server.endpoint.clone());
+
+  let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  assert_eq!(data.hello, "world");
+  assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+  let server = flaky_server(5).await;
+  let client = retrying_client(server.endpoint.clone());
+
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::HttpStatus(503));
+  assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_mutations_by_default() {
+  let server = flaky_server(1).await;
+  let client = retrying_client(server.endpoint.clone());
+
+  let error = client
+    .query::<Hello>("mutation Hello($name: String = \"{\") { hello(name: $name) }")
+    .await
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::HttpStatus(503));
+  assert_eq!(server.requests().len(), 1);
+
+  let client =
+    Client::new_with_config(ClientConfig::new(&server.endpoint).with_retry(RetryPolicy {
+      base_delay: 1,
+      retry_mutations: true,
+      ..Default::default()
+    }));
+  let data = client
+    .query_unwrap::<Hello>("mutation { hello }")
+    .await
+    .unwrap();
+  assert_eq!(data.hello, "world");
+}
+
+#[tokio::test]
+async fn retries_connection_failures() {
+  // connections are closed before a response is sent
+  let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let endpoint = format!("http://{}", listener.local_addr().unwrap());
+  let accepted = Arc::new(Mutex::new(Vec::new()));
+  let times = accepted.clone();
+  tokio::spawn(async move {
+    while let Ok((stream, _)) = listener.accept().await {
+      times.lock().unwrap().push(Instant::now());
+      drop(stream);
+    }
+  });
+  let client = Client::new_with_config(ClientConfig::new(endpoint).with_retry(RetryPolicy {
+    base_delay: 100,
+    max_delay: 150,
+    ..Default::default()
+  }));
+
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Transport);
+
+  let accepted = accepted.lock().unwrap();
+  assert_eq!(accepted.len(), 3);
+  // jitter picks a delay between half and all of the backoff, which doubles up to max_delay
+  let first = accepted[1] - accepted[0];
+  let second = accepted[2] - accepted[1];
+  assert!(first >= Duration::from_millis(50), "{:?}", first);
+  assert!(second >= Duration::from_millis(75), "{:?}", second);
+}
+
+#[tokio::test]
+async fn caps_retry_after_with_max_delay() {
+  let (server, received) = retry_after_server(|| "1".to_string()).await;
+  let client = retrying_client(server.endpoint.clone());
+
+  let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  assert_eq!(data.hello, "world");
+
+  let received = received.lock().unwrap();
+  let delay = received[1] - received[0];
+  // longer than the backoff of a millisecond, shorter than the second asked by the server
+  assert!(delay >= Duration::from_millis(50), "{:?}", delay);
+  assert!(delay < Duration::from_secs(1), "{:?}", delay);
+}
+
+#[tokio::test]
+async fn honors_retry_after_dates() {
+  let (server, received) =
+    retry_after_server(|| httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(2)))
+      .await;
+  let client =
+    Client::new_with_config(ClientConfig::new(&server.endpoint).with_retry(RetryPolicy {
+      base_delay: 1,
+      ..Default::default()
+    }));
+
+  let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  assert_eq!(data.hello, "world");
+
+  let received = received.lock().unwrap();
+  // the date has a precision of a second
+  let delay = received[1] - received[0];
+  assert!(delay >= Duration::from_secs(1), "{:?}", delay);
+}
@@ -0,0 +1,380 @@
+use gql_client::{Schema, TypeKind};
+use serde_json::{json, Value};
+
+fn named(kind: &str, name: &str) -> Value {
+  json!({ "kind": kind, "name": name, "ofType": null })
+}
+
+fn non_null(of_type: Value) -> Value {
+  json!({ "kind": "NON_NULL", "name": null, "ofType": of_type })
+}
+
+fn list(of_type: Value) -> Value {
+  json!({ "kind": "LIST", "name": null, "ofType": of_type })
+}
+
+fn field(name: &str, ty: Value) -> Value {
+  json!({ "name": name, "description": null, "args": [], "type": ty, "isDeprecated": false, "deprecationReason": null })
+}
+
+fn input(name: &str, ty: Value, default_value: Option<&str>) -> Value {
+  json!({ "name": name, "description": null, "type": ty, "defaultValue": default_value })
+}
+
+fn schema() -> Schema {
+  let mut search = field("search", list(named("UNION", "SearchResult")));
+  search["description"] = json!("Search \"anything\"");
+  search["args"] = json!([
+    {
+      "name": "text",
+      "description": "Text to look for",
+      "type": non_null(named("SCALAR", "String")),
+      "defaultValue": null
+    },
+    input("first", named("SCALAR", "Int"), Some("10")),
+  ]);
+  let mut name = field("name", named("SCALAR", "String"));
+  name["isDeprecated"] = json!(true);
+  name["deprecationReason"] = json!("Use \"fullName\"");
+
+  serde_json::from_value(json!({
+    "queryType": { "name": "Root" },
+    "mutationType": null,
+    "subscriptionType": null,
+    "types": [
+      {
+        "kind": "OBJECT",
+        "name": "Root",
+        "description": "Entry point\n\nof the API",
+        "fields": [
+          search,
+          field("node", named("INTERFACE", "Node")),
+          field("users", non_null(list(non_null(named("OBJECT", "User"))))),
+        ],
+        "inputFields": null,
+        "interfaces": [],
+        "enumValues": null,
+        "possibleTypes": null
+      },
+      {
+        "kind": "INTERFACE",
+        "name": "Node",
+        "description": null,
+        "fields": [field("id", non_null(named("SCALAR", "ID")))],
+        "inputFields": null,
+        "interfaces": [],
+        "enumValues": null,
+        "possibleTypes": [named("OBJECT", "User")]
+      },
+      {
+        "kind": "OBJECT",
+        "name": "User",
+        "description": "A user",
+        "fields": [
+          field("id", non_null(named("SCALAR", "ID"))),
+          name,
+          field("role", named("ENUM", "Role")),
+          field("createdAt", named("SCALAR", "DateTime")),
+        ],
+        "inputFields": null,
+        "interfaces": [named("INTERFACE", "Node")],
+        "enumValues": null,
+        "possibleTypes": null
+      },
+      {
+        "kind": "UNION",
+        "name": "SearchResult",
+        "description": null,
+        "fields": null,
+        "inputFields": null,
+        "interfaces": null,
+        "enumValues": null,
+        "possibleTypes": [named("OBJECT", "User"), named("OBJECT", "Root")]
+      },
+      {
+        "kind": "ENUM",
+        "name": "Role",
+        "description": null,
+        "fields": null,
+        "inputFields": null,
+        "interfaces": null,
+        "enumValues": [
+          { "name": "ADMIN", "description": "All rights", "isDeprecated": false, "deprecationReason": null },
+          { "name": "GUEST", "description": null, "isDeprecated": true, "deprecationReason": "No longer supported" }
+        ],
+        "possibleTypes": null
+      },
+      {
+        "kind": "INPUT_OBJECT",
+        "name": "UserFilter",
+        "description": null,
+        "fields": null,
+        "inputFields": [
+          input("role", named("ENUM", "Role"), Some("ADMIN")),
+          input("ids", list(non_null(named("SCALAR", "ID"))), Some("[]")),
+        ],
+        "interfaces": null,
+        "enumValues": null,
+        "possibleTypes": null
+      },
+      {
+        "kind": "SCALAR",
+        "name": "DateTime",
+        "description": null,
+        "fields": null,
+        "inputFields": null,
+        "interfaces": null,
+        "enumValues": null,
+        "possibleTypes": null
+      },
+      {
+        "kind": "SCALAR",
+        "name": "ID",
+        "description": null,
+        "fields": null,
+        "inputFields": null,
+        "interfaces": null,
+        "enumValues": null,
+        "possibleTypes": null
+      },
+      {
+        "kind": "OBJECT",
+        "name": "__Schema",
+        "description": null,
+        "fields": [field("description", named("SCALAR", "String"))],
+        "inputFields": null,
+        "interfaces": [],
+        "enumValues": null,
+        "possibleTypes": null
+      }
+    ],
+    "directives": [
+      {
+        "name": "include",
+        "description": null,
+        "locations": ["FIELD"],
+        "args": [input("if", non_null(named("SCALAR", "Boolean")), None)]
+      },
+      {
+        "name": "cacheControl",
+        "description": "Cache hints",
+        "locations": ["FIELD_DEFINITION", "OBJECT"],
+        "args": [input("maxAge", named("SCALAR", "Int"), Some("0"))]
+      }
+    ]
+  }))
+  .unwrap()
+}
+
+const SDL: &str = r#"schema {
+  query: Root
+}
+
+"""Cache hints"""
+directive @cacheControl(maxAge: Int = 0) on FIELD_DEFINITION | OBJECT
+
+"""
+Entry point
+
+of the API
+"""
+type Root {
+  """
+  Search "anything"
+  """
+  search(
+    """Text to look for"""
+    text: String!
+    first: Int = 10
+  ): [SearchResult]
+  node: Node
+  users: [User!]!
+}
+
+interface Node {
+  id: ID!
+}
+
+"""A user"""
+type User implements Node {
+  id: ID!
+  name: String @deprecated(reason: "Use \"fullName\"")
+  role: Role
+  createdAt: DateTime
+}
+
+union SearchResult = User | Root
+
+enum Role {
+  """All rights"""
+  ADMIN
+  GUEST @deprecated
+}
+
+input UserFilter {
+  role: Role = ADMIN
+  ids: [ID!] = []
+}
+
+scalar DateTime
+"#;
+
+#[test]
+fn prints_the_schema_as_sdl() {
+  assert_eq!(schema().to_sdl(), SDL);
+}
+
+#[test]
+fn leaves_out_the_schema_block_for_default_root_names() {
+  let mut schema = schema();
+  schema.query_type = Some("Query".to_string());
+  assert!(schema.to_sdl().starts_with("\"\"\"Cache hints\"\"\""));
+}
+
+#[test]
+fn prints_the_schema_block_when_a_default_root_name_is_not_a_root() {
+  let schema =
+    Schema::from_sdl("schema { query: Query } type Query { a: Int } type Mutation { b: Int }")
+      .unwrap();
+  assert_eq!(schema.mutation_type, None);
+
+  let sdl = schema.to_sdl();
+  assert!(sdl.starts_with("schema {\n  query: Query\n}\n"));
+  assert_eq!(Schema::from_sdl(&sdl).unwrap(), schema);
+}
+
+#[test]
+fn writes_the_sdl_to_a_file() {
+  let path = std::env::temp_dir().join(format!("gql_client_schema_{}.graphql", std::process::id()));
+  schema().write_sdl(&path).unwrap();
+  let written = std::fs::read_to_string(&path);
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(written.unwrap(), SDL);
+
+  let error = schema()
+    .write_sdl(std::env::temp_dir().join("missing").join("schema.graphql"))
+    .unwrap_err();
+  assert!(error.message().starts_with("Can not write schema to"));
+}
+
+#[test]
+fn parses_printed_sdl() {
+  let parsed = Schema::from_sdl(SDL).unwrap();
+  assert_eq!(parsed.to_sdl(), SDL);
+
+  let schema = schema();
+  assert_eq!(parsed.query_type, schema.query_type);
+  let user = parsed.get_type("User").unwrap();
+  assert_eq!(user, schema.get_type("User").unwrap());
+  let node = parsed.get_type("Node").unwrap();
+  assert_eq!(
+    node.possible_types,
+    schema.get_type("Node").unwrap().possible_types
+  );
+  assert_eq!(
+    parsed.get_type("Root").unwrap().fields[0]
+      .description
+      .as_deref(),
+    Some("Search \"anything\"")
+  );
+}
+
+#[test]
+fn parses_sdl_written_by_hand() {
+  let schema = Schema::from_sdl(
+    r#"
+    # comments and commas are ignored
+    type Query {
+      "Find users"
+      users(filter: UserFilter = { roles: [ADMIN, GUEST], name: "a" }, first: Int = -1,): [User!]!
+      legacy: String @deprecated @cacheControl(maxAge: 10)
+    }
+
+    type User @key(fields: "id") {
+      id: ID!
+    }
+
+    extend type User {
+      email: String @deprecated(reason: """Use "contact" instead""")
+    }
+
+    enum Role { ADMIN GUEST }
+
+    input UserFilter {
+      roles: [Role!]
+      name: String
+    }
+
+    union Entity = | User
+
+    directive @cacheControl(maxAge: Int) repeatable on
+      | FIELD_DEFINITION
+      | OBJECT
+    "#,
+  )
+  .unwrap();
+
+  assert_eq!(schema.query_type.as_deref(), Some("Query"));
+  assert_eq!(schema.mutation_type, None);
+  let users = schema
+    .get_type("Query")
+    .unwrap()
+    .get_field("users")
+    .unwrap();
+  assert_eq!(users.description.as_deref(), Some("Find users"));
+  assert_eq!(
+    users.args[0].default_value.as_deref(),
+    Some(r#"{roles: [ADMIN, GUEST], name: "a"}"#)
+  );
+  assert_eq!(users.args[0].ty.kind, TypeKind::InputObject);
+  assert_eq!(users.args[1].default_value.as_deref(), Some("-1"));
+  assert_eq!(users.ty.to_string(), "[User!]!");
+
+  let legacy = schema
+    .get_type("Query")
+    .unwrap()
+    .get_field("legacy")
+    .unwrap();
+  assert!(legacy.is_deprecated);
+  assert_eq!(
+    legacy.deprecation_reason.as_deref(),
+    Some("No longer supported")
+  );
+
+  let email = schema.get_type("User").unwrap().get_field("email").unwrap();
+  assert_eq!(
+    email.deprecation_reason.as_deref(),
+    Some("Use \"contact\" instead")
+  );
+  assert_eq!(
+    schema.get_type("Entity").unwrap().possible_types[0].kind,
+    TypeKind::Object
+  );
+  let cache_control = schema.get_directive("cacheControl").unwrap();
+  assert_eq!(cache_control.locations, ["FIELD_DEFINITION", "OBJECT"]);
+  assert!(cache_control.is_repeatable);
+}
+
+#[test]
+fn fails_on_invalid_sdl() {
+  let error = Schema::from_sdl("type Query {\n  users: [User!\n}").unwrap_err();
+  assert_eq!(error.message(), "Invalid SDL at 3:1: Expected ']'");
+
+  let error = Schema::from_sdl("type Query { user: User }").unwrap_err();
+  assert_eq!(error.message(), "Invalid SDL: unknown type User");
+
+  let error = Schema::from_sdl("extend type User { id: ID }").unwrap_err();
+  assert_eq!(
+    error.message(),
+    "Invalid SDL at 1:8: Can not extend unknown type User"
+  );
+}
+
+#[test]
+fn prints_repeatable_directives() {
+  let mut schema = schema();
+  schema.directives[1].is_repeatable = true;
+  assert!(schema.to_sdl().contains(
+    "directive @cacheControl(maxAge: Int = 0) repeatable on FIELD_DEFINITION | OBJECT\n"
+  ));
+}
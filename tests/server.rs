@@ -0,0 +1,162 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// Request received by the test server
+#[derive(Clone, Debug)]
+pub struct Request {
+  pub method: String,
+  pub target: String,
+  pub headers: HashMap<String, String>,
+  pub body: Vec<u8>,
+}
+
+/// Response written by the test server, chunks are flushed one by one
+pub struct Response {
+  pub head: String,
+  pub chunks: Vec<String>,
+  pub keep_alive: bool,
+}
+
+impl Response {
+  pub fn json(status: u16, body: impl Into<String>) -> Self {
+    Self::with_headers(status, &[("content-type", "application/json")], body)
+  }
+
+  pub fn with_headers(status: u16, headers: &[(&str, &str)], body: impl Into<String>) -> Self {
+    let body = body.into();
+    let mut head = format!(
+      "HTTP/1.1 {} Status\r\ncontent-length: {}\r\n",
+      status,
+      body.len()
+    );
+    for (name, value) in headers {
+      head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    Self {
+      head,
+      chunks: vec![body],
+      keep_alive: true,
+    }
+  }
+
+  /// A body sent in several writes, the connection is closed at the end of the body
+  pub fn streamed(content_type: &str, chunks: Vec<String>) -> Self {
+    Self {
+      head: format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: {}\r\nconnection: close\r\n",
+        content_type
+      ),
+      chunks,
+      keep_alive: false,
+    }
+  }
+}
+
+pub struct Server {
+  pub endpoint: String,
+  pub connections: Arc<AtomicUsize>,
+  pub requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Server {
+  pub fn requests(&self) -> Vec<Request> {
+    self.requests.lock().unwrap().clone()
+  }
+
+  pub fn connections(&self) -> usize {
+    self.connections.load(Ordering::SeqCst)
+  }
+}
+
+/// Minimal keep-alive http server answering every request with `handler`
+pub async fn serve<F>(handler: F) -> Server
+where
+  F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let connections = Arc::new(AtomicUsize::new(0));
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let handler = Arc::new(handler);
+
+  let server = Server {
+    endpoint: format!("http://{}/graphql", addr),
+    connections: connections.clone(),
+    requests: requests.clone(),
+  };
+
+  tokio::spawn(async move {
+    loop {
+      let (socket, _) = listener.accept().await.unwrap();
+      connections.fetch_add(1, Ordering::SeqCst);
+      let requests = requests.clone();
+      let handler = handler.clone();
+      tokio::spawn(async move {
+        let mut reader = BufReader::new(socket);
+        while let Some(request) = read_request(&mut reader).await {
+          requests.lock().unwrap().push(request.clone());
+          let response = handler(&request);
+
+          let socket = reader.get_mut();
+          socket.write_all(response.head.as_bytes()).await.unwrap();
+          socket.write_all(b"\r\n").await.unwrap();
+          for chunk in &response.chunks {
+            socket.write_all(chunk.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+          }
+          if !response.keep_alive {
+            return;
+          }
+        }
+      });
+    }
+  });
+
+  server
+}
+
+async fn read_request(reader: &mut BufReader<tokio::net::TcpStream>) -> Option<Request> {
+  let mut line = String::new();
+  if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+    return None;
+  }
+  let mut parts = line.split_whitespace();
+  let method = parts.next()?.to_string();
+  let target = parts.next()?.to_string();
+
+  let mut headers = HashMap::new();
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+      return None;
+    }
+    if line == "\r\n" {
+      break;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+  }
+
+  let content_length = headers
+    .get("content-length")
+    .map(|value| value.parse().unwrap())
+    .unwrap_or(0);
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body).await.unwrap();
+
+  Some(Request {
+    method,
+    target,
+    headers,
+    body,
+  })
+}
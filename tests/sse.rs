@@ -0,0 +1,123 @@
+mod server;
+
+use futures_util::StreamExt;
+use gql_client::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::server::{serve, Response};
+
+#[derive(Deserialize, Debug)]
+struct PostAdded {
+  #[serde(rename = "postAdded")]
+  post_added: Post,
+}
+
+#[derive(Deserialize, Debug)]
+struct Post {
+  id: String,
+}
+
+#[derive(Serialize)]
+struct Vars {
+  author: u32,
+}
+
+const SUBSCRIPTION: &str = r#"
+  subscription PostAdded($author: ID!) {
+    postAdded(author: $author) {
+      id
+    }
+  }
+"#;
+
+#[tokio::test]
+async fn streams_server_sent_events() {
+  let server = serve(|_| {
+    Response::streamed(
+      "text/event-stream",
+      vec![
+        ": keep-alive\n\n".to_string(),
+        "event: next\ndata: {\"data\":{\"postAdded\":{\"id\":\"1\"}}}\n\n".to_string(),
+        // events split across chunks, with crlf line endings
+        "event: next\r\ndata: {\"data\":".to_string(),
+        "{\"postAdded\":{\"id\":\"2\"}}}\r\n\r\n".to_string(),
+        "event: complete\ndata:\n\n".to_string(),
+      ],
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let ids: Vec<String> = client
+    .subscribe_sse_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .map(|item| item.unwrap().post_added.id)
+    .collect()
+    .await;
+
+  assert_eq!(ids, vec!["1", "2"]);
+  let request = &server.requests()[0];
+  assert_eq!(request.method, "POST");
+  assert_eq!(request.headers["accept"], "text/event-stream");
+  let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+  assert_eq!(body["variables"]["author"], 1);
+}
+
+#[tokio::test]
+async fn ignores_empty_errors_lists() {
+  let server = serve(|_| {
+    Response::streamed(
+      "text/event-stream",
+      vec![
+        "event: next\ndata: {\"data\":{\"postAdded\":{\"id\":\"1\"}},\"errors\":[]}\n\n"
+          .to_string(),
+        "event: complete\ndata:\n\n".to_string(),
+      ],
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let ids: Vec<String> = client
+    .subscribe_sse_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .map(|item| item.unwrap().post_added.id)
+    .collect()
+    .await;
+
+  assert_eq!(ids, vec!["1"]);
+}
+
+#[tokio::test]
+async fn parses_error_events() {
+  let server = serve(|_| {
+    Response::streamed(
+      "text/event-stream",
+      vec![
+        "event: next\ndata: {\"errors\":[{\"message\":\"Not allowed\"}]}\n\n".to_string(),
+        "event: error\ndata: [{\"message\":\"Subscription failed\"}]\n\n".to_string(),
+      ],
+    )
+  })
+  .await;
+  let client = Client::new(&server.endpoint);
+
+  let items: Vec<_> = client
+    .subscribe_sse::<PostAdded>(SUBSCRIPTION)
+    .await
+    .unwrap()
+    .collect()
+    .await;
+
+  assert_eq!(items.len(), 2);
+  assert!(items[0]
+    .as_ref()
+    .unwrap_err()
+    .contains_error_message("Not allowed"));
+  assert!(items[1]
+    .as_ref()
+    .unwrap_err()
+    .contains_error_message("Subscription failed"));
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "tower")]
+
+mod server;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::{serve, Response};
+use gql_client::{
+  Client, ClientConfig, GraphQLError, GraphQLErrorKind, GraphQLRequest, ReqwestTransport,
+  TransportRequest, TransportResponse,
+};
+use serde::Deserialize;
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+#[derive(Deserialize, Debug)]
+struct Hello {
+  hello: String,
+}
+
+#[tokio::test]
+async fn serves_queries_through_layers() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let service = ServiceBuilder::new()
+    .timeout(Duration::from_secs(5))
+    .service(Client::new(&server.endpoint));
+
+  let request = GraphQLRequest::new("query Hello($name: String) { hello(name: $name) }")
+    .with_variables(HashMap::from([("name", "world")]))
+    .unwrap();
+  let response = service.oneshot(request).await.unwrap();
+  assert_eq!(response.data().unwrap()["hello"], "world");
+
+  let body: serde_json::Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+  assert_eq!(body["variables"]["name"], "world");
+}
+
+#[tokio::test]
+async fn sends_requests_through_a_service() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let config = ClientConfig::new(&server.endpoint);
+  let service = ServiceBuilder::new()
+    .map_request(|mut request: TransportRequest| {
+      request
+        .headers
+        .insert("x-layer".to_string(), "1".to_string());
+      request
+    })
+    .service(ReqwestTransport::new(&config));
+  let client = Client::new_with_service(config, service);
+
+  let data = client.query_unwrap::<Hello>("{ hello }").await.unwrap();
+  assert_eq!(data.hello, "world");
+  assert_eq!(server.requests()[0].headers["x-layer"], "1");
+}
+
+#[tokio::test]
+async fn reports_errors_of_layers() {
+  let slow = service_fn(|_: TransportRequest| async {
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    Ok::<_, GraphQLError>(TransportResponse::new(200, HashMap::new(), "{}"))
+  });
+  let service = ServiceBuilder::new()
+    .timeout(Duration::from_millis(10))
+    .service(slow);
+  let client = Client::new_with_service(ClientConfig::new("http://localhost"), service);
+
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Timeout);
+  assert!(error
+    .source()
+    .unwrap()
+    .is::<tower::timeout::error::Elapsed>());
+
+  let failing = service_fn(|_: TransportRequest| async {
+    Err::<TransportResponse, _>(std::io::Error::other("Connection refused"))
+  });
+  let client = Client::new_with_service(ClientConfig::new("http://localhost"), failing);
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Transport);
+  assert_eq!(error.message(), "Connection refused");
+
+  let failing = service_fn(|_: TransportRequest| async {
+    Err::<TransportResponse, _>(GraphQLError::with_kind(
+      GraphQLErrorKind::Config,
+      "Rejected",
+    ))
+  });
+  let client = Client::new_with_service(ClientConfig::new("http://localhost"), failing);
+  let error = client.query::<Hello>("{ hello }").await.unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Config);
+}
+
+#[tokio::test]
+async fn shares_concurrency_limits_across_requests() {
+  let in_flight = Arc::new(AtomicUsize::new(0));
+  let max_in_flight = Arc::new(AtomicUsize::new(0));
+  let (current, max) = (in_flight.clone(), max_in_flight.clone());
+  let slow = service_fn(move |_: TransportRequest| {
+    let (current, max) = (current.clone(), max.clone());
+    async move {
+      max.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+      tokio::time::sleep(Duration::from_millis(20)).await;
+      current.fetch_sub(1, Ordering::SeqCst);
+      Ok::<_, GraphQLError>(TransportResponse::new(
+        200,
+        HashMap::new(),
+        r#"{"data":{"hello":"world"}}"#,
+      ))
+    }
+  });
+  let service = ServiceBuilder::new().concurrency_limit(1).service(slow);
+  let client = Client::new_with_service(ClientConfig::new("http://localhost"), service);
+  let clone = client.clone();
+
+  let (a, b, c) = tokio::join!(
+    client.query_unwrap::<Hello>("{ hello }"),
+    clone.query_unwrap::<Hello>("{ hello }"),
+    client.query_unwrap::<Hello>("{ hello }")
+  );
+  assert!(a.is_ok() && b.is_ok() && c.is_ok());
+  assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn shares_rate_limits_across_requests() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"hello":"world"}}"#)).await;
+  let config = ClientConfig::new(&server.endpoint);
+  let service = ServiceBuilder::new()
+    .rate_limit(1, Duration::from_millis(100))
+    .service(ReqwestTransport::new(&config));
+  let client = Client::new_with_service(config, service);
+
+  let started = Instant::now();
+  let (a, b) = tokio::join!(
+    client.query_unwrap::<Hello>("{ hello }"),
+    client.query_unwrap::<Hello>("{ hello }")
+  );
+  assert!(a.is_ok() && b.is_ok());
+  assert!(started.elapsed() >= Duration::from_millis(100));
+  assert_eq!(server.requests().len(), 2);
+}
@@ -0,0 +1,63 @@
+#[allow(dead_code)]
+mod structs;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::structs::{inputs::SinglePostVariables, SinglePost};
+use gql_client::{
+  async_trait, Client, ClientConfig, GraphQLError, Transport, TransportRequest, TransportResponse,
+};
+
+#[derive(Clone, Default)]
+struct MockTransport {
+  requests: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    self.requests.lock().unwrap().push(request.clone());
+
+    if request.url == "https://example.com/graphql" {
+      let mut headers = HashMap::new();
+      headers.insert("Location".to_string(), "/moved".to_string());
+      return Ok(TransportResponse::new(301, headers, ""));
+    }
+
+    Ok(TransportResponse::new(
+      200,
+      HashMap::new(),
+      r#"{"data":{"post":{"id":"2"}}}"#,
+    ))
+  }
+}
+
+fn config() -> ClientConfig {
+  ClientConfig::new("https://example.com/graphql").with_header("Authorization", "Bearer token")
+}
+
+#[tokio::test]
+pub async fn sends_requests_through_custom_transport() {
+  let transport = MockTransport::default();
+  let client = Client::new_with_transport(config(), transport.clone());
+
+  let query = "query SinglePostQuery($id: ID!) { post(id: $id) { id } }";
+  let data = client
+    .query_with_vars_unwrap::<SinglePost, SinglePostVariables>(query, SinglePostVariables { id: 2 })
+    .await
+    .unwrap();
+  assert_eq!(data.post.id, "2");
+
+  let requests = transport.requests.lock().unwrap();
+  assert_eq!(requests.len(), 2);
+  assert_eq!(requests[1].url, "https://example.com/moved");
+  assert_eq!(
+    requests[1].headers.get("authorization").map(String::as_str),
+    Some("Bearer token")
+  );
+
+  let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+  assert_eq!(body["query"], query);
+  assert_eq!(body["variables"]["id"], 2);
+}
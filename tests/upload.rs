@@ -0,0 +1,278 @@
+mod server;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::server::{serve, Request, Response};
+use futures_util::io::Cursor;
+use gql_client::{
+  async_trait, Client, ClientConfig, GraphQLError, GraphQLErrorKind, GraphQLRequest, Transport,
+  TransportRequest, TransportResponse, Upload,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+struct Uploaded {
+  upload: bool,
+}
+
+#[derive(Serialize)]
+struct File {
+  file: Upload,
+}
+
+#[derive(Serialize)]
+struct Files {
+  files: Vec<Upload>,
+}
+
+/// Parts of a multipart body, as (headers, content)
+fn parts(request: &Request) -> Vec<(String, String)> {
+  let content_type = &request.headers["content-type"];
+  let boundary = content_type
+    .strip_prefix("multipart/form-data; boundary=")
+    .unwrap();
+  let body = String::from_utf8(request.body.clone()).unwrap();
+  let body = body.strip_suffix(&format!("--{}--\r\n", boundary)).unwrap();
+  body
+    .split(&format!("--{}\r\n", boundary))
+    .skip(1)
+    .map(|part| {
+      let part = part.strip_suffix("\r\n").unwrap();
+      let (headers, content) = part.split_once("\r\n\r\n").unwrap();
+      (headers.to_string(), content.to_string())
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn sends_uploads_as_multipart() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"upload":true}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let file = Upload::from_bytes("hello", "hello.txt").with_content_type("text/plain");
+  let data = client
+    .query_with_vars_unwrap::<Uploaded, _>(
+      "mutation Upload($file: Upload!) { upload(file: $file) }",
+      File { file },
+    )
+    .await
+    .unwrap();
+  assert!(data.upload);
+
+  let requests = server.requests();
+  assert_eq!(requests[0].method, "POST");
+  assert_eq!(requests[0].headers["apollo-require-preflight"], "true");
+  let parts = parts(&requests[0]);
+  assert_eq!(parts.len(), 3);
+
+  assert_eq!(
+    parts[0].0,
+    "Content-Disposition: form-data; name=\"operations\""
+  );
+  assert_eq!(
+    serde_json::from_str::<Value>(&parts[0].1).unwrap(),
+    json!({
+      "query": "mutation Upload($file: Upload!) { upload(file: $file) }",
+      "operationName": "Upload",
+      "variables": { "file": null },
+    })
+  );
+  assert_eq!(parts[1].0, "Content-Disposition: form-data; name=\"map\"");
+  assert_eq!(
+    serde_json::from_str::<Value>(&parts[1].1).unwrap(),
+    json!({ "0": ["variables.file"] })
+  );
+  assert_eq!(
+    parts[2].0,
+    "Content-Disposition: form-data; name=\"0\"; filename=\"hello.txt\"\r\nContent-Type: text/plain"
+  );
+  assert_eq!(parts[2].1, "hello");
+}
+
+#[tokio::test]
+async fn sends_lists_of_uploads() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"upload":true}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let variables = Files {
+    files: vec![
+      Upload::from_bytes(b"first".to_vec(), "first.bin"),
+      Upload::from_reader(Cursor::new(b"second".to_vec()), "second.txt")
+        .with_content_type("text/plain"),
+    ],
+  };
+  client
+    .query_with_vars_unwrap::<Uploaded, _>(
+      "mutation ($files: [Upload!]!) { upload(files: $files) }",
+      variables,
+    )
+    .await
+    .unwrap();
+
+  let requests = server.requests();
+  let parts = parts(&requests[0]);
+  assert_eq!(parts.len(), 4);
+  assert_eq!(
+    serde_json::from_str::<Value>(&parts[0].1).unwrap()["variables"],
+    json!({ "files": [null, null] })
+  );
+  assert_eq!(
+    serde_json::from_str::<Value>(&parts[1].1).unwrap(),
+    json!({ "0": ["variables.files.0"], "1": ["variables.files.1"] })
+  );
+  assert_eq!(
+    parts[2].0,
+    "Content-Disposition: form-data; name=\"0\"; filename=\"first.bin\"\r\nContent-Type: application/octet-stream"
+  );
+  assert_eq!(parts[2].1, "first");
+  assert_eq!(
+    parts[3].0,
+    "Content-Disposition: form-data; name=\"1\"; filename=\"second.txt\"\r\nContent-Type: text/plain"
+  );
+  assert_eq!(parts[3].1, "second");
+}
+
+#[tokio::test]
+async fn reads_uploads_from_files() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"upload":true}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let path = std::env::temp_dir().join(format!("gql_client_upload_{}.txt", std::process::id()));
+  std::fs::write(&path, "from a file").unwrap();
+  let file = Upload::from_path(&path);
+  std::fs::remove_file(&path).unwrap();
+  client
+    .query_with_vars_unwrap::<Uploaded, _>(
+      "mutation ($file: Upload!) { upload(file: $file) }",
+      File {
+        file: file.unwrap(),
+      },
+    )
+    .await
+    .unwrap();
+
+  let parts = parts(&server.requests()[0]);
+  assert!(parts[2].0.contains(&format!(
+    "filename=\"{}\"",
+    path.file_name().unwrap().to_string_lossy()
+  )));
+  assert_eq!(parts[2].1, "from a file");
+}
+
+/// Transport answering without any async runtime
+#[derive(Clone, Default)]
+struct MockTransport {
+  requests: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+  async fn send(&self, request: TransportRequest) -> Result<TransportResponse, GraphQLError> {
+    self.requests.lock().unwrap().push(request);
+    Ok(TransportResponse::new(
+      200,
+      HashMap::new(),
+      r#"{"data":{"upload":true}}"#,
+    ))
+  }
+}
+
+#[test]
+fn sends_files_outside_of_tokio() {
+  let transport = MockTransport::default();
+  let client = Client::new_with_transport(
+    ClientConfig::new("http://localhost/graphql"),
+    transport.clone(),
+  );
+
+  let path = std::env::temp_dir().join(format!("gql_client_executor_{}.txt", std::process::id()));
+  std::fs::write(&path, "from a file").unwrap();
+  let file = Upload::from_path(&path);
+  std::fs::remove_file(&path).unwrap();
+  let data = futures::executor::block_on(client.query_with_vars_unwrap::<Uploaded, _>(
+    "mutation ($file: Upload!) { upload(file: $file) }",
+    File {
+      file: file.unwrap(),
+    },
+  ))
+  .unwrap();
+  assert!(data.upload);
+
+  let body = String::from_utf8(transport.requests.lock().unwrap()[0].body.clone()).unwrap();
+  assert!(body.contains("\r\n\r\nfrom a file\r\n"));
+}
+
+#[test]
+fn fails_to_upload_missing_files() {
+  let path = std::env::temp_dir().join("gql_client_missing_upload.txt");
+  let error = Upload::from_path(&path).unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Other);
+  assert!(error.message().contains("gql_client_missing_upload.txt"));
+}
+
+#[tokio::test]
+async fn fails_when_a_reader_was_already_read() {
+  let server = serve(|_| Response::json(200, r#"{"data":{"upload":true}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  let file = Upload::from_reader(Cursor::new(b"once".to_vec()), "once.txt");
+  let query = "mutation ($file: Upload!) { upload(file: $file) }";
+  client
+    .query_with_vars_unwrap::<Uploaded, _>(query, File { file: file.clone() })
+    .await
+    .unwrap();
+  let error = client
+    .query_with_vars_unwrap::<Uploaded, _>(query, File { file })
+    .await
+    .unwrap_err();
+  assert!(error.message().contains("already been read"));
+  assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn sends_variables_without_uploads_as_they_are() {
+  #[derive(Serialize)]
+  struct Vars {
+    zone: u32,
+    amount: u128,
+  }
+
+  let server = serve(|_| Response::json(200, r#"{"data":{"upload":false}}"#)).await;
+  let client = Client::new(&server.endpoint);
+
+  client
+    .query_with_vars_unwrap::<Uploaded, _>(
+      "query ($zone: Int, $amount: BigInt) { upload }",
+      Vars {
+        zone: 1,
+        amount: u128::MAX,
+      },
+    )
+    .await
+    .unwrap();
+
+  let body = String::from_utf8(server.requests()[0].body.clone()).unwrap();
+  assert!(body.contains(&format!(
+    r#""variables":{{"zone":1,"amount":{}}}"#,
+    u128::MAX
+  )));
+}
+
+#[test]
+fn fails_to_serialize_uploads_outside_of_queries() {
+  let file = || File {
+    file: Upload::from_bytes("hello", "hello.txt"),
+  };
+
+  let error = serde_json::to_value(file()).unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Upload hello.txt can only be sent in the variables of a query"
+  );
+  let error = GraphQLRequest::new("mutation ($file: Upload!) { upload(file: $file) }")
+    .with_variables(file())
+    .unwrap_err();
+  assert_eq!(error.kind(), GraphQLErrorKind::Serialize);
+}
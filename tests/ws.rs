@@ -0,0 +1,314 @@
+#![cfg(feature = "ws")]
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use gql_client::{Client, ClientConfig, GraphQLErrorKind, WsProtocol};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+#[derive(Deserialize, Debug)]
+struct PostAdded {
+  #[serde(rename = "postAdded")]
+  post_added: Post,
+}
+
+#[derive(Deserialize, Debug)]
+struct Post {
+  id: String,
+}
+
+#[derive(Serialize)]
+struct Vars {
+  author: u32,
+}
+
+async fn receive<S>(socket: &mut WebSocketStream<S>) -> Value
+where
+  S: AsyncRead + AsyncWrite + Unpin,
+{
+  let message = socket.next().await.unwrap().unwrap();
+  serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+async fn send<S>(socket: &mut WebSocketStream<S>, message: Value)
+where
+  S: AsyncRead + AsyncWrite + Unpin,
+{
+  socket
+    .send(Message::text(message.to_string()))
+    .await
+    .unwrap();
+}
+
+/// Accept a single subscription, returns the socket and the id of the subscription.
+/// The server accepts `protocol` if the client offers it and speaks it afterwards.
+async fn accept(
+  listener: TcpListener,
+  protocol: &'static str,
+) -> (WebSocketStream<TcpStream>, Value) {
+  let (stream, _) = listener.accept().await.unwrap();
+  #[allow(clippy::result_large_err)]
+  let callback = |request: &Request, mut response: Response| {
+    let offered = request.headers().get("sec-websocket-protocol").unwrap();
+    assert!(offered.to_str().unwrap().split(", ").any(|p| p == protocol));
+    response
+      .headers_mut()
+      .insert("sec-websocket-protocol", HeaderValue::from_static(protocol));
+    Ok(response)
+  };
+  let mut socket = tokio_tungstenite::accept_hdr_async(stream, callback)
+    .await
+    .unwrap();
+  let legacy = protocol == "graphql-ws";
+
+  assert_eq!(receive(&mut socket).await["type"], "connection_init");
+  send(&mut socket, json!({"type": "connection_ack"})).await;
+
+  let subscribe = receive(&mut socket).await;
+  assert_eq!(
+    subscribe["type"],
+    if legacy { "start" } else { "subscribe" }
+  );
+  assert_eq!(subscribe["payload"]["variables"]["author"], 1);
+  let id = subscribe["id"].clone();
+
+  if legacy {
+    send(&mut socket, json!({"type": "ka"})).await;
+  } else {
+    send(&mut socket, json!({"type": "ping"})).await;
+    assert_eq!(receive(&mut socket).await["type"], "pong");
+  }
+
+  (socket, id)
+}
+
+/// Serve a single subscription which sends the given payloads
+async fn serve(protocol: &'static str, payloads: Vec<Value>) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+
+  tokio::spawn(async move {
+    let legacy = protocol == "graphql-ws";
+    let (mut socket, id) = accept(listener, protocol).await;
+
+    let next = if legacy { "data" } else { "next" };
+    for payload in payloads {
+      send(
+        &mut socket,
+        json!({"id": id, "type": next, "payload": payload}),
+      )
+      .await;
+    }
+    send(&mut socket, json!({"id": id, "type": "complete"})).await;
+  });
+
+  format!("http://{}/graphql", addr)
+}
+
+const SUBSCRIPTION: &str = r#"
+  subscription PostAdded($author: ID!) {
+    postAdded(author: $author) {
+      id
+    }
+  }
+"#;
+
+#[tokio::test]
+async fn streams_subscription_results() {
+  let endpoint = serve(
+    "graphql-transport-ws",
+    vec![
+      json!({"data": {"postAdded": {"id": "1"}}}),
+      json!({"data": {"postAdded": {"id": "2"}}}),
+    ],
+  )
+  .await;
+  let client = Client::new(endpoint);
+
+  let ids: Vec<String> = client
+    .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .map(|item| item.unwrap().post_added.id)
+    .collect()
+    .await;
+
+  assert_eq!(ids, vec!["1", "2"]);
+}
+
+#[tokio::test]
+async fn yields_graphql_errors_from_next_payloads() {
+  let endpoint = serve(
+    "graphql-transport-ws",
+    vec![json!({"errors": [{"message": "Not allowed"}]})],
+  )
+  .await;
+  let client = Client::new(endpoint);
+
+  let items: Vec<_> = client
+    .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .collect()
+    .await;
+
+  assert_eq!(items.len(), 1);
+  let error = items[0].as_ref().unwrap_err();
+  assert!(error.contains_error_message("Not allowed"));
+}
+
+#[tokio::test]
+async fn negotiates_legacy_graphql_ws_protocol() {
+  let endpoint = serve(
+    "graphql-ws",
+    vec![json!({"data": {"postAdded": {"id": "1"}}})],
+  )
+  .await;
+  let client = Client::new(endpoint);
+
+  let ids: Vec<String> = client
+    .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .map(|item| item.unwrap().post_added.id)
+    .collect()
+    .await;
+
+  assert_eq!(ids, vec!["1"]);
+}
+
+#[tokio::test]
+async fn uses_protocol_forced_by_config() {
+  let endpoint = serve(
+    "graphql-ws",
+    vec![json!({"data": {"postAdded": {"id": "1"}}})],
+  )
+  .await;
+  let client =
+    Client::new_with_config(ClientConfig::new(endpoint).with_ws_protocol(WsProtocol::GraphQLWs));
+
+  let items: Vec<_> = client
+    .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .unwrap()
+    .collect()
+    .await;
+
+  assert_eq!(items.len(), 1);
+  assert!(items[0].is_ok());
+}
+
+/// Serve a single subscription which sends one payload, then reports the message ending it
+async fn serve_until_stopped(protocol: &'static str) -> (String, oneshot::Receiver<Value>) {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let (sender, receiver) = oneshot::channel();
+
+  tokio::spawn(async move {
+    let next = if protocol == "graphql-ws" {
+      "data"
+    } else {
+      "next"
+    };
+    let (mut socket, id) = accept(listener, protocol).await;
+    let payload = json!({"data": {"postAdded": {"id": "1"}}});
+    send(
+      &mut socket,
+      json!({"id": id, "type": next, "payload": payload}),
+    )
+    .await;
+    let _ = sender.send(receive(&mut socket).await);
+  });
+
+  (format!("http://{}/graphql", addr), receiver)
+}
+
+#[tokio::test]
+async fn stops_the_subscription_when_the_stream_is_dropped() {
+  for (protocol, ws_protocol, stop) in [
+    (
+      "graphql-transport-ws",
+      WsProtocol::GraphQLTransportWs,
+      "complete",
+    ),
+    ("graphql-ws", WsProtocol::GraphQLWs, "stop"),
+  ] {
+    let (endpoint, stopped) = serve_until_stopped(protocol).await;
+    let client = Client::new_with_config(ClientConfig::new(endpoint).with_ws_protocol(ws_protocol));
+
+    let mut stream = client
+      .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+      .await
+      .unwrap();
+    let post = stream.next().await.unwrap().unwrap();
+    assert_eq!(post.post_added.id, "1");
+    drop(stream);
+
+    let message = tokio::time::timeout(Duration::from_secs(5), stopped)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(message, json!({"type": stop, "id": "1"}));
+  }
+}
+
+#[tokio::test]
+async fn times_out_when_the_server_never_acks() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    let (stream, _) = listener.accept().await.unwrap();
+    #[allow(clippy::result_large_err)]
+    let callback = |_: &Request, mut response: Response| {
+      response.headers_mut().insert(
+        "sec-websocket-protocol",
+        HeaderValue::from_static("graphql-transport-ws"),
+      );
+      Ok(response)
+    };
+    let mut socket = tokio_tungstenite::accept_hdr_async(stream, callback)
+      .await
+      .unwrap();
+    assert_eq!(receive(&mut socket).await["type"], "connection_init");
+    // keep the connection open without ever sending connection_ack
+    let _ = socket.next().await;
+  });
+  let endpoint = format!("http://{}/graphql", addr);
+  let client = Client::new_with_config(ClientConfig::new(endpoint).with_timeout(1));
+
+  let result = tokio::time::timeout(
+    Duration::from_secs(5),
+    client.subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 }),
+  )
+  .await
+  .unwrap();
+
+  let error = result.err().unwrap();
+  assert_eq!(error.kind(), GraphQLErrorKind::Timeout);
+}
+
+#[tokio::test]
+async fn fails_when_the_server_names_no_protocol() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    let (stream, _) = listener.accept().await.unwrap();
+    let _ = tokio_tungstenite::accept_async(stream).await;
+  });
+  let client = Client::new(format!("http://{}/graphql", addr));
+
+  let error = client
+    .subscribe_with_vars::<PostAdded, Vars>(SUBSCRIPTION, Vars { author: 1 })
+    .await
+    .err()
+    .unwrap();
+  assert_eq!(error.kind(), GraphQLErrorKind::Protocol);
+}